@@ -0,0 +1,169 @@
+/// Provides reachable code of ROM, which is found by walking control flow from entry point.
+///
+/// Conditional skips follow both ways, subroutine calls follow both target and return address,
+/// and BNNN computed jumps are not followed because their targets are known only at runtime.
+pub struct CodeMap {
+    /// Address where ROM is loaded.
+    pub base: u16,
+    /// Reachable (address, word) pairs in ascending order of address.
+    pub code: Vec<(u16, u16)>,
+    /// (address, target) of subroutine calls.
+    pub calls: Vec<(u16, u16)>,
+    /// (address, target) of jumps and calls whose target is outside of ROM.
+    pub outside: Vec<(u16, u16)>,
+    /// Addresses of BNNN computed jumps.
+    pub computed_jumps: Vec<u16>,
+}
+
+impl CodeMap {
+    /// Walk reachable code of given ROM bytes which is loaded at `base`, from `entry` address.
+    pub fn walk(rom: &[u8], base: u16, entry: u16) -> CodeMap {
+        CodeMap::walk_from(rom, base, entry, true)
+    }
+
+    /// Walk code of one subroutine which starts at `entry`. Called subroutines are not followed.
+    fn walk_subroutine(rom: &[u8], base: u16, entry: u16) -> CodeMap {
+        CodeMap::walk_from(rom, base, entry, false)
+    }
+
+    fn walk_from(rom: &[u8], base: u16, entry: u16, is_call_followed: bool) -> CodeMap {
+        let mut map = CodeMap { base, code: Vec::new(), calls: Vec::new(), outside: Vec::new(), computed_jumps: Vec::new() };
+        let mut visited = vec![false; rom.len()];
+        let mut pending = vec![entry];
+
+        while let Some(address) = pending.pop() {
+            let word = match word_at(rom, base, address) {
+                Some(word) => word,
+                None => continue,
+            };
+            let offset = (address - base) as usize;
+            if visited[offset] {
+                continue;
+            }
+            visited[offset] = true;
+            map.code.push((address, word));
+
+            let next = address.wrapping_add(2);
+            // Skip over 4 bytes long instruction F000 NNNN of XO-CHIP.
+            let skipped = match word_at(rom, base, next) {
+                Some(0xF000) => address.wrapping_add(6),
+                _ => address.wrapping_add(4),
+            };
+            let target = word & 0x0FFF;
+            let nexts: Vec<u16> = match word >> 12 {
+                0x0 if word == 0x00EE || word == 0x00FD => vec![],                      // RET, EXIT (SCHIP)
+                0x1 if target == address => vec![],                                    // Infinite loop.
+                0x1 => { map.check_target(rom, address, target); vec![target] },
+                0x2 => {
+                    map.calls.push((address, target));
+                    map.check_target(rom, address, target);
+                    if is_call_followed { vec![target, next] } else { vec![next] }
+                },
+                0x3 | 0x4 | 0x5 | 0x9 => vec![next, skipped],
+                0xB => { map.computed_jumps.push(address); vec![] },
+                0xE if word & 0xFF == 0x9E || word & 0xFF == 0xA1 => vec![next, skipped],
+                0xF if word == 0xF000 => vec![address.wrapping_add(4)],                 // F000 NNNN (XO-CHIP)
+                _ => vec![next],
+            };
+            pending.extend(nexts.into_iter().rev());
+        }
+
+        map.code.sort_unstable();
+        map.outside.sort_unstable();
+        map
+    }
+
+    /// Estimate maximum depth of nested subroutine calls from entry point.
+    /// `None` is returned if recursive call is found.
+    pub fn call_depth(&self, rom: &[u8], entry: u16) -> Option<usize> {
+        let mut depths: Vec<(u16, Option<usize>)> = Vec::new();
+        self.subroutine_depth(rom, entry, &mut depths)
+    }
+
+    /// Get call depth of subroutine at `entry`. Depth of visiting subroutine is memoized as `None`.
+    fn subroutine_depth(&self, rom: &[u8], entry: u16, depths: &mut Vec<(u16, Option<usize>)>) -> Option<usize> {
+        if let Some(&(_, depth)) = depths.iter().find(|&&(address, _)| address == entry) {
+            return depth;
+        }
+        depths.push((entry, None));
+
+        let subroutine = CodeMap::walk_subroutine(rom, self.base, entry);
+        let mut depth = 0;
+        for &(_, target) in &subroutine.calls {
+            if word_at(rom, self.base, target).is_some() {
+                depth = depth.max(self.subroutine_depth(rom, target, depths)? + 1);
+            }
+        }
+        if let Some(memo) = depths.iter_mut().find(|(address, _)| *address == entry) {
+            memo.1 = Some(depth);
+        }
+        Some(depth)
+    }
+
+    /// Get sprite data regions as [start, end) addresses, which are drawn by DXYN after setting I with ANNN.
+    /// DXY0 is regarded as 16x16 sprite of 32 bytes. Overlapping regions are merged.
+    pub fn sprite_regions(&self) -> Vec<(u16, u16)> {
+        let mut regions: Vec<(u16, u16)> = Vec::new();
+        for (index, &(_, word)) in self.code.iter().enumerate() {
+            if word >> 12 != 0xA {
+                continue;
+            }
+            if let Some(draw) = self.next_use_of_i(index).filter(|draw| draw >> 12 == 0xD) {
+                let start = word & 0x0FFF;
+                let size = match draw & 0xF { 0 => 32, n => n };
+                regions.push((start, start + size));
+            }
+        }
+
+        regions.sort_unstable();
+        let mut merged: Vec<(u16, u16)> = Vec::new();
+        for (start, end) in regions {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// Get the next instruction which uses I after instruction of given index, in the same straight-line code.
+    /// `None` is returned if I is set again, or control flow is changed before I is used.
+    pub fn next_use_of_i(&self, index: usize) -> Option<u16> {
+        let mut address = self.code[index].0;
+        for &(next_address, word) in &self.code[index + 1..] {
+            if Some(next_address) != address.checked_add(2) {
+                return None;
+            }
+            address = next_address;
+            match word >> 12 {
+                0xA | 0x1 | 0x2 | 0xB => return None,
+                0x0 if word == 0x00EE => return None,
+                0xD => return Some(word),
+                0xF if word == 0xF000 => return None,
+                0xF => match word & 0xFF {
+                    0x1E | 0x33 | 0x55 | 0x65 | 0x02 => return Some(word),
+                    0x29 | 0x30 => return None,
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+        None
+    }
+
+    /// Record target of jump or call if it is outside of ROM.
+    fn check_target(&mut self, rom: &[u8], address: u16, target: u16) {
+        if word_at(rom, self.base, target).is_none() {
+            self.outside.push((address, target));
+        }
+    }
+}
+
+/// Get big-endian word of given address from ROM bytes which is loaded at `base`.
+pub fn word_at(rom: &[u8], base: u16, address: u16) -> Option<u16> {
+    let offset = address.checked_sub(base)? as usize;
+    match (rom.get(offset), rom.get(offset + 1)) {
+        (Some(&high), Some(&low)) => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    }
+}
@@ -0,0 +1,84 @@
+/// Provides the way how sound timer is shown on terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BeepIndicator {
+    /// Do not show sound timer.
+    Off,
+    /// Show speaker glyph with intensity bar under display.
+    Glyph,
+    /// Flash display background toward foreground color.
+    Flash,
+}
+
+impl BeepIndicator {
+    /// Get canonical name.
+    pub fn name(self) -> &'static str {
+        match self {
+            BeepIndicator::Off => "off",
+            BeepIndicator::Glyph => "glyph",
+            BeepIndicator::Flash => "flash",
+        }
+    }
+
+    /// Get indicator from given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<BeepIndicator> {
+        match &name.to_lowercase()[..] {
+            "off" | "none" => Some(BeepIndicator::Off),
+            "glyph" | "speaker" => Some(BeepIndicator::Glyph),
+            "flash" => Some(BeepIndicator::Flash),
+            _ => None,
+        }
+    }
+}
+
+/// Maximum intensity level of visual beep.
+pub const MAX_BEEP_LEVEL: u8 = 4;
+/// Frame count of continuous beep which reaches maximum intensity level.
+const FULL_BEEP_FRAMES: u32 = 30;
+
+/// Provides visual beep state which is driven by sound timer for silent terminals.
+///
+/// Intensity follows how long sound timer stays non-zero. Short beep is shown weakly,
+/// long beep grows to maximum level, and indicator fades out one level per frame after beep.
+pub struct VisualBeep {
+    pub indicator: BeepIndicator,
+    /// Count of continuous frames which sound timer is non-zero.
+    frames: u32,
+    level: u8,
+}
+
+impl VisualBeep {
+    /// Create new visual beep with given indicator.
+    pub fn new(indicator: BeepIndicator) -> VisualBeep {
+        VisualBeep { indicator, frames: 0, level: 0 }
+    }
+
+    /// Update intensity with sound state of this frame.
+    /// Return true if intensity level is changed.
+    pub fn update(&mut self, is_beep: bool) -> bool {
+        let old_level = self.level;
+        if is_beep {
+            self.frames += 1;
+            let grown = (self.frames.min(FULL_BEEP_FRAMES) * (MAX_BEEP_LEVEL as u32 - 1)) / FULL_BEEP_FRAMES;
+            self.level = 1 + grown as u8;
+        } else {
+            self.frames = 0;
+            self.level = self.level.saturating_sub(1);
+        }
+        self.level != old_level
+    }
+
+    /// Get intensity level ranged in [0, MAX_BEEP_LEVEL].
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Get speaker glyph with intensity bar. Glyph always has the same width.
+    pub fn glyph(&self) -> String {
+        const BARS: [char; MAX_BEEP_LEVEL as usize] = ['\u{2582}', '\u{2584}', '\u{2586}', '\u{2588}'];
+        let mut glyph = String::from(if self.level > 0 { "\u{266A}" } else { " " });
+        for (index, &bar) in BARS.iter().enumerate() {
+            glyph.push(if (index as u8) < self.level { bar } else { ' ' });
+        }
+        glyph
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers};
+
+use super::check::Arguments;
+use super::device::Device;
+use super::machine::Machine;
+use super::romname::RomName;
+use super::screenshot;
+
+/// File extensions of ROM which are listed in browser.
+const ROM_EXTENSIONS: [&str; 1] = ["ch8"];
+/// Maximum depth of sub directories which are scanned.
+const MAX_SCAN_DEPTH: usize = 4;
+/// Maximum count of recently played ROMs which are kept.
+const MAX_HISTORY: usize = 20;
+/// Frame count which ROM is run headlessly for thumbnail preview. (3 seconds)
+const THUMBNAIL_FRAMES: u32 = 180;
+/// Frame count of thumbnail preview which is run at once while no key is pending.
+const THUMBNAIL_STEP_FRAMES: u32 = 10;
+/// Seed of random number generator of thumbnail preview, so preview is the same every time.
+const THUMBNAIL_SEED: u64 = 0;
+
+/// Provides one ROM file which is listed in browser.
+struct BrowserEntry {
+    path: String,
+    name: RomName,
+    size: u64,
+    is_recent: bool,
+}
+
+/// Provides resolved preview of ROM, which is made when the entry is selected first.
+struct Preview {
+    thumbnail: Vec<String>,
+    lines: Vec<String>,
+}
+
+/// Provides preview which is being made, by running ROM headlessly a few frames at a time.
+struct PendingPreview {
+    path: String,
+    /// Machine of ROM. It is `None` if ROM could not be loaded.
+    machine: Option<Machine>,
+    ipf: u32,
+    /// Count of frames which are already run.
+    frame: u32,
+    thumbnail: Vec<String>,
+    lines: Vec<String>,
+}
+
+/// Provides terminal ROM browser of directory.
+///
+/// ROM files are listed with metadata parsed from file names, recently played ROMs come first,
+/// and typed characters filter the list incrementally. Selected ROM is previewed with thumbnail
+/// which is made by running the ROM headlessly for a few seconds.
+pub struct Browser {
+    dir: String,
+    entries: Vec<BrowserEntry>,
+    /// Recently played ROM paths, the most recent one first.
+    history: Vec<String>,
+    query: String,
+    /// Indices of entries which match query.
+    matched: Vec<usize>,
+    /// Index of selected one in `matched`.
+    selected: usize,
+    /// Index of the first visible one in `matched`.
+    scroll: usize,
+    previews: HashMap<String, Preview>,
+    /// Preview of selected entry which is being made.
+    pending: Option<PendingPreview>,
+    /// Message which is shown at the bottom line, such as error of the last run.
+    message: Option<String>,
+}
+
+impl Browser {
+    /// Create new browser of given directory. ROM files in sub directories are also listed.
+    pub fn new(dir: &str) -> Result<Browser, String> {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("`{}` is not directory", dir));
+        }
+        let mut browser = Browser {
+            dir: dir.to_string(),
+            entries: Vec::new(),
+            history: history_path().map(|path| load_history(&path)).unwrap_or_default(),
+            query: String::new(),
+            matched: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            previews: HashMap::new(),
+            pending: None,
+            message: None,
+        };
+        browser.scan();
+        Ok(browser)
+    }
+
+    /// Set message which is shown at the bottom line.
+    pub fn set_message(&mut self, message: &str) {
+        self.message = Some(message.lines().next().unwrap_or("").to_string());
+    }
+
+    /// Show browser until ROM is selected or browser is quitted. Selected ROM path is returned.
+    /// `resolve` gets settings of ROM, and `build` creates machine with the settings, which are used for preview.
+    pub fn run(&mut self, resolve: &dyn Fn(&str) -> Result<Arguments, String>,
+        build: &dyn Fn(&Arguments) -> Result<Machine, String>) -> Result<Option<String>, String> {
+        let mut device = Device::new().map_err(|err| format!("Error : {:?}", err))?;
+        self.draw(&mut device)?;
+
+        loop {
+            // Start preview of selected ROM after the list is shown. Preview of unselected ROM is dropped.
+            if let Some(path) = self.selected_path().filter(|path| !self.previews.contains_key(*path)) {
+                if self.pending.as_ref().map(|pending| &pending.path[..]) != Some(path) {
+                    self.pending = Some(PendingPreview::new(path, resolve, build));
+                }
+            }
+
+            // Preview is run only while no key is pending, because it takes a while.
+            let timeout = match self.pending {
+                Some(_) => Duration::from_millis(0),
+                None => Duration::from_millis(100),
+            };
+            if !poll(timeout).map_err(|err| format!("{:?}", err))? {
+                let is_finished = self.pending.as_mut().is_some_and(|pending| pending.step(THUMBNAIL_STEP_FRAMES));
+                if let Some(pending) = self.pending.take_if(|_| is_finished) {
+                    let path = pending.path.clone();
+                    self.previews.insert(path, pending.finish());
+                    self.draw(&mut device)?;
+                }
+                continue;
+            }
+            let event = read().map_err(|err| format!("{:?}", err))?;
+            if let Event::Key(_) = event {
+                self.message = None;
+            }
+            match event {
+                Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers }) if modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(None);
+                },
+                Event::Key(KeyEvent { code, .. }) => match code {
+                    KeyCode::Esc if self.query.is_empty() => return Ok(None),
+                    KeyCode::Esc => self.set_query(String::new()),
+                    KeyCode::Enter => if let Some(path) = self.selected_path().map(String::from) {
+                        self.add_history(&path);
+                        return Ok(Some(path));
+                    },
+                    KeyCode::Up => self.select(self.selected.saturating_sub(1)),
+                    KeyCode::Down => self.select(self.selected + 1),
+                    KeyCode::PageUp => self.select(self.selected.saturating_sub(self.list_rows(&device))),
+                    KeyCode::PageDown => self.select(self.selected + self.list_rows(&device)),
+                    KeyCode::Home => self.select(0),
+                    KeyCode::End => self.select(self.matched.len().saturating_sub(1)),
+                    KeyCode::Backspace => {
+                        let mut query = self.query.clone();
+                        query.pop();
+                        self.set_query(query);
+                    },
+                    KeyCode::Char(chr) => self.set_query(format!("{}{}", self.query, chr)),
+                    _ => continue,
+                },
+                Event::Resize(_, _) => (),
+                _ => continue,
+            }
+            self.draw(&mut device)?;
+        }
+    }
+
+    /// Scan ROM files of directory, and sort them as recently played ones first.
+    fn scan(&mut self) {
+        let mut paths = Vec::new();
+        scan_dir(Path::new(&self.dir), 0, &mut paths);
+        paths.sort();
+
+        // Recently played ROMs may be out of directory.
+        let recent: Vec<String> = self.history.iter()
+            .filter(|path| Path::new(path).is_file())
+            .cloned()
+            .collect();
+        let others = paths.into_iter().filter(|path| !recent.contains(path));
+        self.entries = recent.iter().map(|path| (path.clone(), true))
+            .chain(others.map(|path| (path, false)))
+            .map(|(path, is_recent)| BrowserEntry {
+                name: RomName::from_path(&path),
+                size: fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0),
+                path,
+                is_recent,
+            })
+            .collect();
+        self.set_query(self.query.clone());
+    }
+
+    /// Set search query, and filter entries whose file name or metadata contains it. Case is ignored.
+    fn set_query(&mut self, query: String) {
+        let needle = query.to_lowercase();
+        self.matched = self.entries.iter().enumerate()
+            .filter(|(_, entry)| {
+                let file_name = Path::new(&entry.path).file_name().map(|name| name.to_string_lossy().to_lowercase());
+                file_name.is_some_and(|name| name.contains(&needle))
+                    || entry.name.display_name().to_lowercase().contains(&needle)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.query = query;
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    /// Select matched entry of given index, which is clamped into the list.
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.matched.len().saturating_sub(1));
+    }
+
+    fn selected_path(&self) -> Option<&str> {
+        self.matched.get(self.selected).map(|&index| &self.entries[index].path[..])
+    }
+
+    /// Put given ROM path at the front of history, and save history.
+    fn add_history(&mut self, path: &str) {
+        let path = fs::canonicalize(path).map(|path| path.to_string_lossy().into_owned()).unwrap_or_else(|_| path.to_string());
+        self.history.retain(|recent| recent != &path);
+        self.history.insert(0, path);
+        self.history.truncate(MAX_HISTORY);
+        // Played ROM comes first when browser is shown again.
+        self.scan();
+        if let Some(history_path) = history_path() {
+            if let Err(err) = save_history(&history_path, &self.history) {
+                self.set_message(&err);
+            }
+        }
+    }
+
+    /// Get row count of list.
+    fn list_rows(&self, device: &Device) -> usize {
+        let rows = device.size().map(|(_, rows)| rows).unwrap_or(24);
+        rows.saturating_sub(4).max(1) as usize
+    }
+
+    /// Draw whole browser into terminal.
+    fn draw(&mut self, device: &mut Device) -> Result<(), String> {
+        let error = |err| format!("{:?}", err);
+        let (columns, rows) = match device.size() {
+            Ok((columns, rows)) if columns > 0 && rows > 0 => (columns as usize, rows as usize),
+            _ => (80, 24),
+        };
+        let list_rows = self.list_rows(device);
+        let list_width = (columns / 2).clamp(20, 60);
+
+        // Keep selected entry visible.
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + list_rows {
+            self.scroll = self.selected + 1 - list_rows;
+        }
+
+        device.clear().map_err(error)?;
+        let header = format!("chipmunk : {} ({} / {} ROMs)", self.dir, self.matched.len(), self.entries.len());
+        device.mv_print_attr((0, 0), &fit(&header, columns), false).map_err(error)?;
+        device.mv_print_attr((0, 1), &fit(&format!("Search : {}_", self.query), columns), false).map_err(error)?;
+
+        for (row, &index) in self.matched.iter().skip(self.scroll).take(list_rows).enumerate() {
+            let entry = &self.entries[index];
+            let marker = if entry.is_recent { "* " } else { "  " };
+            let text = fit(&format!("{}{}", marker, entry.name.display_name()), list_width);
+            let is_selected = self.scroll + row == self.selected;
+            device.mv_print_attr((0, (row + 3) as u16), &text, is_selected).map_err(error)?;
+        }
+        if self.matched.is_empty() {
+            device.mv_print_attr((0, 3), &fit("  No ROM file (.ch8)", list_width), false).map_err(error)?;
+        }
+
+        // Preview of selected entry at the right side.
+        let panel_x = list_width + 2;
+        let panel_width = columns.saturating_sub(panel_x);
+        if let (Some(&index), true) = (self.matched.get(self.selected), panel_width > 0) {
+            let entry = &self.entries[index];
+            let mut lines = Vec::new();
+            match self.previews.get(&entry.path) {
+                Some(preview) => {
+                    lines.extend(preview.thumbnail.iter().cloned());
+                    lines.push(String::new());
+                    lines.extend(preview.lines.iter().cloned());
+                },
+                None => lines.push(String::from("Loading preview...")),
+            }
+            lines.push(format!("Size   : {} bytes", entry.size));
+            lines.push(format!("File   : {}", entry.path));
+            if let Some(description) = &entry.name.description {
+                lines.push(String::new());
+                lines.extend(description.lines().map(String::from));
+            }
+            for (row, line) in lines.iter().take(rows.saturating_sub(4)).enumerate() {
+                device.mv_print_attr((panel_x as u16, (row + 3) as u16), &fit(line, panel_width), false).map_err(error)?;
+            }
+        }
+
+        let footer = match &self.message {
+            Some(message) => message.clone(),
+            None => String::from("Enter run  Up/Down select  type to search  Esc clear / quit  * recently played"),
+        };
+        device.mv_print_attr((0, (rows - 1) as u16), &fit(&footer, columns), true).map_err(error)?;
+        device.present().map_err(|err| err.to_string())?;
+        Ok(())
+    }
+}
+
+impl PendingPreview {
+    /// Start preview of ROM with settings resolved by `resolve`, and machine created by `build`.
+    fn new(path: &str, resolve: &dyn Fn(&str) -> Result<Arguments, String>,
+        build: &dyn Fn(&Arguments) -> Result<Machine, String>) -> PendingPreview {
+        let mut preview = PendingPreview {
+            path: path.to_string(),
+            machine: None,
+            ipf: 0,
+            frame: 0,
+            thumbnail: Vec::new(),
+            lines: Vec::new(),
+        };
+        let mut arguments = match resolve(path) {
+            Ok(arguments) => arguments,
+            Err(err) => {
+                preview.lines.push(err);
+                return preview;
+            },
+        };
+        if let Some(entry) = &arguments.rom_entry {
+            preview.lines.push(format!("Title  : {}", entry.title));
+            preview.lines.push(format!("Author : {}", entry.authors.join(", ")));
+            preview.lines.push(format!("Year   : {}", entry.release.as_deref().unwrap_or("-")));
+        } else {
+            let name = RomName::from_path(path);
+            preview.lines.push(format!("Title  : {}", name.title));
+            preview.lines.push(format!("Author : {}", name.author.as_deref().unwrap_or("-")));
+            preview.lines.push(format!("Year   : {}", name.year.as_deref().unwrap_or("-")));
+            if !name.tags.is_empty() {
+                preview.lines.push(format!("Tags   : {}", name.tags.join(", ")));
+            }
+        }
+        preview.lines.push(format!("Quirks : {}", arguments.quirks.name()));
+
+        // ROM which is refused by validation is not run.
+        if let Err(err) = arguments.check_refused() {
+            preview.thumbnail.push(err);
+            return preview;
+        }
+
+        // Preview is the same every time, and starts from power on.
+        arguments.seed = Some(THUMBNAIL_SEED);
+        arguments.load_state = None;
+        match build(&arguments) {
+            Ok(machine) => preview.machine = Some(machine),
+            Err(err) => preview.thumbnail.push(err),
+        }
+        preview.ipf = arguments.ipf;
+        preview
+    }
+
+    /// Run ROM headlessly by given frame count, and return true if preview is finished.
+    /// Screen until error is used if ROM halts.
+    fn step(&mut self, frames: u32) -> bool {
+        let machine = match self.machine.as_mut() {
+            Some(machine) => machine,
+            None => return true,
+        };
+        for _ in 0..frames.min(THUMBNAIL_FRAMES.saturating_sub(self.frame)) {
+            if machine.run_frame(self.ipf).is_err() {
+                self.lines.push(String::from("Halted in preview"));
+                self.frame = THUMBNAIL_FRAMES;
+                break;
+            }
+            self.frame += 1;
+        }
+        self.frame >= THUMBNAIL_FRAMES
+    }
+
+    /// Finish preview with thumbnail of current screen.
+    fn finish(self) -> Preview {
+        let thumbnail = match &self.machine {
+            Some(machine) => screenshot::to_braille(&machine.screen),
+            None => self.thumbnail,
+        };
+        Preview { thumbnail, lines: self.lines }
+    }
+}
+
+/// Collect ROM file paths in directory and its sub directories.
+fn scan_dir(dir: &Path, depth: usize, paths: &mut Vec<String>) {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(_) => return,
+    };
+    for path in read_dir.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.is_dir() && depth < MAX_SCAN_DEPTH {
+            scan_dir(&path, depth + 1, paths);
+        } else if is_rom_file(&path) {
+            // Absolute path is used to match with history.
+            let path = fs::canonicalize(&path).unwrap_or(path);
+            paths.push(path.to_string_lossy().into_owned());
+        }
+    }
+}
+
+/// Check given path has ROM file extension. Extension is not case-sensitive.
+fn is_rom_file(path: &Path) -> bool {
+    path.is_file() && path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| ROM_EXTENSIONS.contains(&&extension[..]))
+}
+
+/// Get path of recently played ROM history.
+/// `CHIPMUNK_HISTORY` environment variable, `$XDG_STATE_HOME/chipmunk/history`
+/// or `$HOME/.local/state/chipmunk/history` is used in order.
+pub fn history_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("CHIPMUNK_HISTORY") {
+        return Some(PathBuf::from(path));
+    }
+    let state_home = env::var("XDG_STATE_HOME").map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| Path::new(&home).join(".local").join("state")))
+        .ok()?;
+    Some(state_home.join("chipmunk").join("history"))
+}
+
+/// Load history which has one ROM path per line.
+fn load_history(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .map(|text| text.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Save history which has one ROM path per line.
+fn save_history(path: &Path, history: &[String]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("Could not create `{}` : {}", dir.display(), err))?;
+    }
+    let mut text = history.join("\n");
+    text.push('\n');
+    fs::write(path, text).map_err(|err| format!("Could not write `{}` : {}", path.display(), err))
+}
+
+/// Truncate or pad given text into exact `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let count = fitted.chars().count();
+    fitted.extend(std::iter::repeat_n(' ', width - count));
+    fitted
+}
@@ -0,0 +1,46 @@
+use std::{
+    fs,
+    io::{self, Write},
+    time,
+};
+
+use super::json::Value;
+
+/// Provides asciinema v2 `.cast` recorder.
+/// Bytes written to terminal are recorded as output events with elapsed time.
+pub struct CastRecorder {
+    writer: io::BufWriter<fs::File>,
+    start_time: time::Instant,
+}
+
+impl CastRecorder {
+    /// Create new cast recorder into given file path, with terminal size (columns, rows).
+    pub fn new(path: &str, (width, height): (u16, u16)) -> Result<CastRecorder, String> {
+        let file = fs::File::create(path).map_err(|err| format!("Could not create `{}` : {}", path, err))?;
+        let mut writer = io::BufWriter::new(file);
+
+        let timestamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let term = std::env::var("TERM").unwrap_or_else(|_| String::from("xterm-256color"));
+        writeln!(writer,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"env\": {{\"TERM\": {}}}}}",
+            width, height, timestamp, Value::String(term).to_pretty_string())
+            .map_err(|err| format!("Could not write `{}` : {}", path, err))?;
+
+        Ok(CastRecorder { writer, start_time: time::Instant::now() })
+    }
+
+    /// Record given bytes which are written to terminal now.
+    pub fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let elapsed = self.start_time.elapsed().as_secs_f64();
+        let data = Value::String(String::from_utf8_lossy(bytes).into_owned());
+        writeln!(self.writer, "[{:.6}, \"o\", {}]", elapsed, data.to_pretty_string())
+    }
+
+    /// Flush recorded events into file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -1,72 +1,696 @@
-use crate::engine::isa::{to_bitfield_string, parse_instruction};
-use std::{
-    fs, env,
-    io::Read
-};
-
-fn is_file_valid_ch8(path: &str) -> bool {
-    use std::path::Path;
-
-    let path = Path::new(&path);
-    if !(path.exists() && path.is_file()) {
-        // If file is not exist, and not file, just return false.
-        return false;
-    } 
-
-    // Read file and check validation.
-    let file = {
-        if let Ok(file) = fs::File::open(path) {
-            file
-        } else {
-            return false;
+use crate::engine::render::{RenderMode, FlickerFilter};
+use crate::engine::beep::BeepIndicator;
+use crate::engine::quirks::{QuirkProfile, Quirks};
+use crate::engine::machine::Machine;
+use crate::engine::config::apply_configs;
+use crate::engine::database::{self, Database, RomEntry};
+use crate::engine::sha1::sha1_hex;
+use crate::engine::analysis::CodeMap;
+use crate::engine::detect::{self, Confidence, Detection};
+use crate::engine::validate::{validate, Issue, Severity};
+use crate::engine::info::ReportFormat;
+use crate::engine::layout::{self, MemoryLayout};
+use std::{fs, env, path::Path, cell::OnceCell};
+
+/// Exit code when program is finished successfully.
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code when machine is halted by error, or test is failed.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code when command-line arguments are invalid.
+pub const EXIT_USAGE: i32 = 2;
+/// Exit code when ROM file does not exist, or is not valid.
+pub const EXIT_INVALID_ROM: i32 = 3;
+
+/// Provides subcommand of command-line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Run ROM in terminal, or without terminal. (default)
+    Run,
+    /// Print disassembly listing of ROM.
+    Disasm,
+    /// Print information of ROM.
+    Info,
+    /// Run ROM without terminal as fast as possible, and print performance.
+    Bench,
+    /// Run ROM without terminal, and print or compare the last screen.
+    Test,
+    /// Print effective settings of ROM. (`config show`)
+    ConfigShow,
+    /// Browse ROM files of directory, and run selected one. (no ROM file, or directory is given)
+    Browse,
+    /// Print help.
+    Help,
+}
+
+impl Command {
+    /// Get subcommand from given `name`.
+    fn from_name(name: &str) -> Option<Command> {
+        match name {
+            "run" => Some(Command::Run),
+            "disasm" => Some(Command::Disasm),
+            "info" => Some(Command::Info),
+            "bench" => Some(Command::Bench),
+            "test" => Some(Command::Test),
+            "help" => Some(Command::Help),
+            _ => None,
         }
-    };
+    }
+}
+
+/// Provides parsed command-line arguments.
+pub struct Arguments {
+    /// Subcommand.
+    pub command: Command,
+    /// Valid CHIP-8 file path.
+    pub file_path: String,
+    /// Quirk profile of platform given by `--quirks`.
+    pub quirks: QuirkProfile,
+    /// Quirk overrides given by `--quirk [no-]<name>`, which are applied over quirks of profile and ROM database.
+    pub quirk_overrides: Vec<String>,
+    /// Instructions per frame given by `--ipf`.
+    pub ipf: u32,
+    /// Speed multiplier of terminal run given by `--speed`.
+    pub speed: f64,
+    /// Seed of random number generator given by `--seed`.
+    pub seed: Option<u64>,
+    /// Save state file path to load before running, given by `--load-state`.
+    pub load_state: Option<String>,
+    /// Frame count to run by `bench` and `test`, given by `--frames`.
+    pub frames: u64,
+    /// Expected screen PBM image of `test`, given by `--expect`.
+    pub expect: Option<String>,
+    /// Show status line under display, given by `--status-bar`.
+    pub status_bar: bool,
+    /// Keymap preset name or keymap file path given by `--keymap`.
+    pub keymap: Option<String>,
+    /// Key binding overrides given by `--bind <host key>=<chip-8 key>`.
+    pub binds: Vec<(String, String)>,
+    /// Screen render mode given by `--renderer`.
+    pub render_mode: RenderMode,
+    /// Built-in theme name or theme file path given by `--theme`.
+    pub theme: Option<String>,
+    /// Theme color overrides given by `--color0` ~ `--color3`, `--background` and `--foreground`.
+    pub colors: Vec<(String, String)>,
+    /// Anti-flicker filter given by `--anti-flicker`.
+    pub flicker_filter: FlickerFilter,
+    /// Frame to take screenshot without terminal, given by `--screenshot-at`.
+    pub screenshot_at: Option<u64>,
+    /// Screenshot file path given by `--screenshot`.
+    pub screenshot: Option<String>,
+    /// Integer scale of PNG screenshot given by `--scale`.
+    pub scale: usize,
+    /// Frame count to run without terminal, given by `--headless`.
+    pub headless: Option<u64>,
+    /// Animated GIF file path given by `--record-gif`.
+    pub record_gif: Option<String>,
+    /// Screen is sampled into GIF at every this frames, given by `--gif-every`.
+    pub gif_every: u64,
+    /// Y4M video file path given by `--record-y4m`. `-` is standard output.
+    pub record_y4m: Option<String>,
+    /// asciinema cast file path which records terminal output, given by `--record-cast`.
+    pub record_cast: Option<String>,
+    /// Do not ring terminal bell while sound timer is active, given by `--no-bell`.
+    pub no_bell: bool,
+    /// WAV file path which records sound, given by `--record-wav`.
+    pub record_wav: Option<String>,
+    /// Raw 16-bit PCM output path given by `--record-pcm`. `-` is standard output.
+    pub record_pcm: Option<String>,
+    /// Tone frequency of sound in Hz, given by `--tone`.
+    pub tone: f32,
+    /// Volume of sound in percent, given by `--volume`.
+    pub volume: u8,
+    /// Visual indicator of sound timer given by `--visual-beep`.
+    pub visual_beep: BeepIndicator,
+    /// Input movie file path to play back, given by `--input-movie`.
+    pub input_movie: Option<String>,
+    /// Input movie file path to record pressed keys, given by `--record-input`.
+    pub record_input: Option<String>,
+    /// Print written byte count to terminal when program ends, given by `--render-stats`.
+    pub render_stats: bool,
+    /// Output format of info report given by `--format`.
+    pub format: ReportFormat,
+    /// Refuse to run ROM which has any validation warning, given by `--strict`.
+    pub strict: bool,
+    /// Reload ROM when it is changed, given by `--watch`.
+    pub watch: bool,
+    /// Source file which is also watched, such as `.8o` file, given by `--watch-source`.
+    pub watch_source: Option<String>,
+    /// Shell command which builds ROM from source before reload, given by `--build`.
+    pub build: Option<String>,
+    /// Re-apply input until the current frame after reload, given by `--watch-replay`.
+    pub watch_replay: bool,
+    /// Memory layout preset given by `--layout`. Layout of quirk profile is used if not given.
+    pub layout: Option<String>,
+    /// Address where ROM is loaded, given by `--load-address`.
+    pub load_address: Option<u16>,
+    /// Initial program counter given by `--entry`. Load address is used if not given.
+    pub entry_point: Option<u16>,
+    /// Address of font data given by `--font-address`.
+    pub font_address: Option<u16>,
+    /// Size of whole memory given by `--memory-size`.
+    pub memory_size: Option<usize>,
+    /// Reserved regions as [start, end) addresses given by `--reserved`, which replace ones of layout.
+    pub reserved: Vec<(usize, usize)>,
+    /// Config files and tables which are applied, in order.
+    pub config_layers: Vec<String>,
+    /// SHA-1 hash of ROM file as lowercase hexadecimal, which is key of ROM database and per-ROM config.
+    pub rom_hash: String,
+    /// Known information of ROM found in ROM database.
+    pub rom_entry: Option<RomEntry>,
+    /// Platform detected by scanning ROM code, when ROM is not found in ROM database.
+    pub detection: Option<Detection>,
+}
+
+impl Default for Arguments {
+    fn default() -> Self {
+        Arguments {
+            command: Command::Run,
+            file_path: String::new(),
+            quirks: QuirkProfile::default(),
+            quirk_overrides: Vec::new(),
+            ipf: Machine::instructions_per_frame(),
+            speed: 1.0,
+            seed: None,
+            load_state: None,
+            frames: 600,
+            expect: None,
+            status_bar: false,
+            keymap: None,
+            binds: Vec::new(),
+            render_mode: RenderMode::HalfBlock,
+            theme: None,
+            colors: Vec::new(),
+            flicker_filter: FlickerFilter::Off,
+            screenshot_at: None,
+            screenshot: None,
+            scale: 8,
+            headless: None,
+            record_gif: None,
+            gif_every: 1,
+            record_y4m: None,
+            record_cast: None,
+            no_bell: false,
+            record_wav: None,
+            record_pcm: None,
+            tone: 440.0,
+            volume: 25,
+            visual_beep: BeepIndicator::Off,
+            input_movie: None,
+            record_input: None,
+            render_stats: false,
+            format: ReportFormat::default(),
+            strict: false,
+            watch: false,
+            watch_source: None,
+            build: None,
+            watch_replay: false,
+            layout: None,
+            load_address: None,
+            entry_point: None,
+            font_address: None,
+            memory_size: None,
+            reserved: Vec::new(),
+            config_layers: Vec::new(),
+            rom_hash: String::new(),
+            rom_entry: None,
+            detection: None,
+        }
+    }
+}
+
+/// Options which do not take value.
+const FLAG_OPTIONS: [&str; 6] = ["status-bar", "render-stats", "no-bell", "strict", "watch", "watch-replay"];
+
+/// Get short usage message.
+pub fn usage() -> String {
+    String::from("Valid usage : ./chipmunk [run|disasm|info|bench|test|config show] [options...] <valid ch8 file path>\n\
+                  Valid usage : ./chipmunk [run] [options...] [ROM directory]\n\
+                  See `./chipmunk --help` for all commands and options.")
+}
+
+/// Get help message of all commands and options.
+pub fn help() -> String {
+    String::from("\
+chipmunk : CHIP-8 interpreter simulator
+
+Usage : ./chipmunk [command] [options...] <ch8 file path>
+        ./chipmunk [options...] [directory]
+
+Commands :
+    run       Run ROM in terminal. (default)
+              If directory or nothing is given, ROM browser of the directory is opened.
+    disasm    Print disassembly listing of ROM.
+    info      Print information report of ROM. With `--format json`, report is printed as JSON.
+    bench     Run ROM without terminal as fast as possible, and print performance.
+    test      Run ROM without terminal, and print the last screen.
+              With `--expect <pbm>`, compare the last screen and exit with 1 if different.
+    config show
+              Print effective settings of ROM, resolved from config files and options.
+    help      Print this message.
+
+Machine options :
+    --quirks <cosmac|schip|xochip>     Quirk profile of platform. (default cosmac)
+    --quirk [no-]<name>                Enable or disable one of shift, load-store, jump and vf-reset quirks.
+    --ipf <n>                          Instructions per frame. (default 29333)
+    --speed <multiplier>               Speed of terminal run. (default 1)
+    --seed <n>                         Seed of random number generator.
+    --load-state <path>                Load save state before running.
+    --frames <n>                       Frame count of bench and test. (default 600)
+    --expect <pbm path>                Expected last screen of test.
+    --format <text|json>               Output format of info report. (default text)
+    --strict                           Refuse to run ROM which has validation warnings.
+
+Input options :
+    --keymap <preset|file>             qwerty, azerty, qwertz, dvorak, numpad-hex or keymap file.
+    --bind <host key>=<chip-8 key>     Override one key binding. Can be repeated.
+    --input-movie <path>               Play back input movie.
+    --record-input <path>              Record pressed keys as input movie.
 
-    enum InstructionState { Left, Right, }
-    let mut instruction: [u8; 2] = [0, 0];
-    let mut parse_state = InstructionState::Left;
-    let mut address = 0x200;
-    for byte in file.bytes() {
-        // Error check
-        if let Err(_) = byte { return false; }
-
-        // Parse
-        let byte = byte.unwrap();
-        let (next_state, check_instruction) = match parse_state {
-            InstructionState::Left => { instruction[0] = byte; (InstructionState::Right, false) },
-            InstructionState::Right => { instruction[1] = byte; (InstructionState::Left, true) }
+Display options :
+    --renderer <block|half|braille>    Render mode. (default half)
+    --theme <name|file>                default, green, amber, lcd, inverted, octo or theme file.
+    --color0 ~ --color3 <#RRGGBB>      Override theme color. (`--background`, `--foreground`)
+    --anti-flicker <off|persist|fade>  Anti-flicker filter. (default off)
+    --status-bar                       Show status line under display.
+    --visual-beep <off|glyph|flash>    Show sound timer visually.
+    --render-stats                     Print written byte count when finished.
+
+Sound options :
+    --tone <hz>                        Tone frequency. (default 440)
+    --volume <0-100>                   Tone volume. (default 25)
+    --no-bell                          Do not ring terminal bell.
+    --record-wav <path>                Record sound as WAV file.
+    --record-pcm <path|->              Write sound as raw 16-bit PCM stream. (`-` only with `--headless`)
+
+Recording options :
+    --headless <frames>                Run without terminal until given frame.
+    --screenshot-at <frame>            Take screenshot at given frame (from 1) without terminal.
+    --screenshot <png|pbm|pgm path>    Screenshot file path.
+    --scale <n>                        Pixel scale of images and videos. (default 8)
+    --record-gif <path>                Record animated GIF.
+    --gif-every <n>                    Sample screen into GIF every n frames. (default 1)
+    --record-y4m <path|->              Record Y4M video. (`-` only with `--headless`)
+    --record-cast <path>               Record terminal output as asciinema cast file.
+
+Memory options :
+    --layout <default|vip|eti660|xochip>
+                                       Memory layout preset. (default is layout of quirk profile)
+    --load-address <address>           Address where ROM is loaded, such as 0x600.
+    --entry <address>                  Initial program counter. (default load address)
+    --font-address <address>           Address of font data.
+    --memory-size <bytes>              Size of whole memory, up to 65536.
+    --reserved <start>-<end>           Reserved region which ROM should not overlap. Can be repeated.
+
+Watch options :
+    --watch                            Reload ROM when it is changed, keeping terminal session.
+    --watch-source <path>              Also watch source file such as `.8o` file.
+    --build <command>                  Shell command which builds ROM before reload.
+    --watch-replay                     Re-apply input until the current frame after reload.
+
+Config options :
+    --config <path>                    User config file instead of default one.
+    --no-config                        Do not load any config file.
+    --database <dir>                   ROM database directory of community CHIP-8 database format,
+                                       which has `programs.json` and `sha1-hashes.json`.
+                                       (default `~/.local/share/chipmunk/database` if it exists)
+    --no-database                      Do not look up ROM database.
+    --no-detect                        Do not detect platform of unknown ROM from its code.
+
+    Settings are applied as defaults < ROM database or detected platform < user config < per-ROM config < options.
+    User config is `$XDG_CONFIG_HOME/chipmunk/config.toml` or `~/.config/chipmunk/config.toml`,
+    and per-ROM config is `[rom.\"<file name>\"]` or `[rom.<sha1>]` table of it, and `<rom>.toml`.
+
+Hotkeys :
+    Esc quit, F5 pause, F6 frame advance, F7 instruction step, F8 reset,
+    F9 / F10 speed down / up, F2 save state, F12 screenshot.
+
+Exit codes :
+    0 success, 1 runtime error or test failure, 2 invalid arguments,
+    3 invalid ROM file, or ROM refused by validation errors (or warnings with `--strict`).")
+}
+
+/// Parse value of option, or return error which names the option.
+fn parse_value<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value `{}` of `--{}`", value, option))
+}
+
+impl Arguments {
+    /// Apply one option `name` (without `--`) with `value`.
+    /// `value` must be given if option is not flag.
+    pub fn apply_option(&mut self, name: &str, value: Option<&str>) -> Result<(), String> {
+        let is_flag = FLAG_OPTIONS.contains(&name);
+        let value = match (value, is_flag) {
+            (Some(value), _) => value,
+            (None, true) => "true",
+            (None, false) => return Err(format!("Option `--{}` needs value", name)),
         };
+        let invalid = || format!("Invalid value `{}` of `--{}`", value, name);
+
+        match name {
+            "quirks" => self.quirks = QuirkProfile::from_name(value).ok_or_else(invalid)?,
+            "quirk" => {
+                QuirkProfile::default().quirks().apply_override(value).ok_or_else(invalid)?;
+                self.quirk_overrides.push(value.to_string());
+            },
+            // Zero instruction per frame makes clock of terminal run infinite.
+            "ipf" => self.ipf = match parse_value(name, value)? { 0 => return Err(invalid()), ipf => ipf },
+            "speed" => self.speed = parse_value(name, value)?,
+            "seed" => self.seed = Some(parse_value(name, value)?),
+            "load-state" => self.load_state = Some(value.to_string()),
+            "frames" => self.frames = parse_value(name, value)?,
+            "expect" => self.expect = Some(value.to_string()),
+            "status-bar" => self.status_bar = parse_value(name, value)?,
+            "keymap" => self.keymap = Some(value.to_string()),
+            "bind" => match value.rfind('=') {
+                Some(pos) => self.binds.push((value[..pos].to_string(), value[pos + 1..].to_string())),
+                None => return Err(invalid()),
+            },
+            "renderer" => self.render_mode = RenderMode::from_name(value).ok_or_else(invalid)?,
+            "theme" => self.theme = Some(value.to_string()),
+            "color0" | "color1" | "color2" | "color3" | "background" | "foreground" => {
+                self.colors.push((name.to_string(), value.to_string()));
+            },
+            "anti-flicker" => self.flicker_filter = FlickerFilter::from_name(value).ok_or_else(invalid)?,
+            "render-stats" => self.render_stats = parse_value(name, value)?,
+            "strict" => self.strict = parse_value(name, value)?,
+            "format" => self.format = ReportFormat::from_name(value).ok_or_else(invalid)?,
+            // Screenshot is taken at the end of frame, so the first frame is 1.
+            "screenshot-at" => self.screenshot_at = match parse_value(name, value)? { 0 => return Err(invalid()), frame => Some(frame) },
+            "screenshot" => self.screenshot = Some(value.to_string()),
+            "scale" => self.scale = parse_value(name, value)?,
+            "headless" => self.headless = Some(parse_value(name, value)?),
+            "record-gif" => self.record_gif = Some(value.to_string()),
+            "gif-every" => self.gif_every = parse_value(name, value)?,
+            "record-y4m" => self.record_y4m = Some(value.to_string()),
+            "record-cast" => self.record_cast = Some(value.to_string()),
+            "no-bell" => self.no_bell = parse_value(name, value)?,
+            "record-wav" => self.record_wav = Some(value.to_string()),
+            "record-pcm" => self.record_pcm = Some(value.to_string()),
+            "tone" => self.tone = parse_value(name, value)?,
+            "volume" => self.volume = parse_value(name, value)?,
+            "visual-beep" => self.visual_beep = BeepIndicator::from_name(value).ok_or_else(invalid)?,
+            "input-movie" => self.input_movie = Some(value.to_string()),
+            "record-input" => self.record_input = Some(value.to_string()),
+            "watch" => self.watch = parse_value(name, value)?,
+            "watch-source" => self.watch_source = Some(value.to_string()),
+            "build" => self.build = Some(value.to_string()),
+            "watch-replay" => self.watch_replay = parse_value(name, value)?,
+            "layout" => {
+                MemoryLayout::from_name(value).ok_or_else(invalid)?;
+                self.layout = Some(value.to_string());
+            },
+            "load-address" => self.load_address = Some(parse_address(value).ok_or_else(invalid)?),
+            "entry" => self.entry_point = Some(parse_address(value).ok_or_else(invalid)?),
+            "font-address" => self.font_address = Some(parse_address(value).ok_or_else(invalid)?),
+            "memory-size" => self.memory_size = Some(layout::parse_number(value).ok_or_else(invalid)?),
+            "reserved" => self.reserved.push(layout::parse_region(value).ok_or_else(invalid)?),
+            _ => return Err(format!("Unknown option `--{}`", name)),
+        }
+        Ok(())
+    }
+}
+
+/// Parse 16-bit address which is decimal, or hexadecimal with `0x` prefix.
+fn parse_address(value: &str) -> Option<u16> {
+    layout::parse_number(value).filter(|&address| address <= u16::MAX as usize).map(|address| address as u16)
+}
+
+impl Arguments {
+    /// Get memory layout, which is preset of `--layout` or quirk profile with given addresses and size.
+    pub fn memory_layout(&self) -> MemoryLayout {
+        let mut layout = self.layout.as_deref()
+            .and_then(MemoryLayout::from_name)
+            .unwrap_or_else(|| MemoryLayout::of_profile(self.quirks));
+        if let Some(address) = self.load_address {
+            layout.load_address = address;
+            layout.entry_point = address;
+        }
+        if let Some(address) = self.entry_point {
+            layout.entry_point = address;
+        }
+        if let Some(address) = self.font_address {
+            layout.font_address = address;
+        }
+        if let Some(size) = self.memory_size {
+            layout.memory_size = size;
+        }
+        if !self.reserved.is_empty() {
+            layout.reserved = self.reserved.clone();
+        }
+        layout
+    }
 
-        // Check instruction
-        if check_instruction {
-            println!(
-                "{:04} : 0x{:02x}{:02x} :: {} :: {:?}", 
-                address, instruction[0], instruction[1], 
-                to_bitfield_string(&instruction, '1', '0'),
-                parse_instruction(&instruction)
-            );
-            address += 0x02; // 2 Bytes
+    /// Get quirks of given quirk profile to run ROM.
+    /// Quirks required by ROM database are kept unless other quirk profile is selected, and `--quirk` overrides are applied.
+    pub fn quirks_of(&self, profile: QuirkProfile) -> Quirks {
+        let entry_quirks = self.rom_entry.as_ref()
+            .filter(|entry| entry.profile == Some(profile))
+            .and_then(|entry| entry.quirks);
+        let mut quirks = entry_quirks.unwrap_or_else(|| profile.quirks());
+        for value in &self.quirk_overrides {
+            // Overrides are already checked by `apply_option()`.
+            let _ = quirks.apply_override(value);
         }
+        quirks
+    }
 
-        // Update flag.
-        parse_state = next_state;
+    /// Validate ROM, and return error if it is refused to run by validation errors (or warnings with `--strict`).
+    pub fn check_refused(&self) -> Result<(), String> {
+        let issues = check_rom(&self.file_path, self.quirks, &self.memory_layout())?;
+        match issues.iter().find(|issue| issue.severity == Severity::Error || self.strict) {
+            Some(issue) => Err(format!("`{}` is refused to run by validation : {}", self.file_path, issue)),
+            None => Ok(()),
+        }
     }
 
-    true
+    /// Apply platform, speed and keypad hints of ROM database entry.
+    fn apply_rom_entry(&mut self, entry: &RomEntry) {
+        if let Some(profile) = entry.profile {
+            self.quirks = profile;
+        }
+        if let Some(tickrate) = entry.tickrate.filter(|&tickrate| tickrate > 0) {
+            self.ipf = tickrate;
+        }
+        for (host, key) in &entry.keys {
+            self.binds.push((host.clone(), format!("{:X}", key)));
+        }
+    }
+
+    /// Get effective settings as TOML text, which can be used as config file.
+    pub fn settings_toml(&self) -> String {
+        let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut lines = vec![
+            format!("quirks = {}", quote(self.quirks.name())),
+            format!("quirk = [{}]", self.quirk_overrides.iter().map(|value| quote(value)).collect::<Vec<_>>().join(", ")),
+            format!("ipf = {}", self.ipf),
+            format!("speed = {}", self.speed),
+            format!("bind = [{}]", self.binds.iter()
+                .map(|(host, key)| quote(&format!("{}={}", host, key)))
+                .collect::<Vec<_>>()
+                .join(", ")),
+            format!("renderer = {}", quote(self.render_mode.name())),
+        ];
+        // Unset options are not written.
+        lines.extend(self.keymap.iter().map(|keymap| format!("keymap = {}", quote(keymap))));
+        lines.extend(self.theme.iter().map(|theme| format!("theme = {}", quote(theme))));
+        lines.extend(self.colors.iter().map(|(name, value)| format!("{} = {}", name, quote(value))));
+        lines.extend(self.layout.iter().map(|name| format!("layout = {}", quote(name))));
+        let addresses = [("load-address", self.load_address), ("entry", self.entry_point), ("font-address", self.font_address)];
+        lines.extend(addresses.iter()
+            .filter_map(|(name, address)| address.map(|address| format!("{} = \"0x{:03X}\"", name, address))));
+        lines.extend(self.memory_size.iter().map(|size| format!("memory-size = {}", size)));
+        if !self.reserved.is_empty() {
+            lines.push(format!("reserved = [{}]", self.reserved.iter()
+                .map(|(start, end)| quote(&format!("0x{:03X}-0x{:03X}", start, end)))
+                .collect::<Vec<_>>()
+                .join(", ")));
+        }
+        lines.extend(self.watch_source.iter().map(|path| format!("watch-source = {}", quote(path))));
+        lines.extend(self.build.iter().map(|command| format!("build = {}", quote(command))));
+        lines.extend(vec![
+            format!("anti-flicker = {}", quote(self.flicker_filter.name())),
+            format!("status-bar = {}", self.status_bar),
+            format!("strict = {}", self.strict),
+            format!("tone = {}", self.tone),
+            format!("volume = {}", self.volume),
+            format!("no-bell = {}", self.no_bell),
+            format!("visual-beep = {}", quote(self.visual_beep.name())),
+        ]);
+        lines.join("\n")
+    }
 }
 
-pub fn get_ch8_file_path(args: &mut env::Args) -> Result<String, String> {
-    // Check given arguments are valid.
-    if args.len() != 2 {
-        return Err(format!("Valid usage : ./{} {}", "sh_chip8.exe", "valid ch8 file path"));
-    } 
+/// Provides parsed command-line, before settings of ROM are resolved.
+/// ROM browser resolves settings of each selected ROM with the same command-line.
+pub struct CommandLine {
+    pub command: Command,
+    /// ROM file path, or directory of ROM browser.
+    pub file_path: Option<String>,
+    /// Options as (name, value) pairs in given order.
+    options: Vec<(String, Option<String>)>,
+    config: Option<String>,
+    is_config_used: bool,
+    database: Option<String>,
+    is_database_used: bool,
+    is_detect_used: bool,
+    /// Database which is loaded once at the first lookup, and reused for every ROM of browser.
+    loaded_database: OnceCell<Database>,
+}
 
-    // Check file is exist, and valid.
-    let file_path: String = args.nth(1).unwrap();
-    if is_file_valid_ch8(&file_path) == true {
-        Ok(file_path)
-    } else {
-        return Err(format!("Valid usage : ./{} {}", "sh_chip8.exe", "valid ch8 file path"))
+impl CommandLine {
+    /// Get ROM database, which is loaded at the first call.
+    /// Database of `--database`, installed database or bundled database is used in order.
+    fn database(&self) -> Result<Option<&Database>, String> {
+        if !self.is_database_used {
+            return Ok(None);
+        }
+        if let Some(database) = self.loaded_database.get() {
+            return Ok(Some(database));
+        }
+        let installed_dir = database::default_dir().filter(|dir| dir.is_dir());
+        let database = match (&self.database, installed_dir) {
+            (Some(dir), _) => Database::load(dir)?,
+            (None, Some(dir)) => Database::load(&dir.to_string_lossy())?,
+            (None, None) => Database::bundled()?,
+        };
+        Ok(Some(self.loaded_database.get_or_init(|| database)))
     }
+
+    /// Resolve settings of given ROM file.
+    /// Settings are applied as defaults < ROM database or detected platform < user config < per-ROM config < command-line options.
+    /// Validation of ROM file is not processed here, use `check_rom()`.
+    pub fn resolve(&self, file_path: &str) -> Result<Arguments, String> {
+        // ROM selected in browser is run.
+        let command = if self.command == Command::Browse { Command::Run } else { self.command };
+        let mut arguments = Arguments { command, ..Arguments::default() };
+        // ROM is hashed only here, and the hash is used by database, config and info report.
+        // It is not hashed by `Memory::new`, because settings from database decide memory layout to load ROM.
+        let rom = fs::read(file_path).map_err(|err| format!("Could not read `{}` : {}", file_path, err))?;
+        arguments.rom_hash = sha1_hex(&rom);
+        if let Some(database) = self.database()? {
+            arguments.rom_entry = database.lookup(&arguments.rom_hash);
+            if let Some(entry) = arguments.rom_entry.clone() {
+                arguments.apply_rom_entry(&entry);
+                arguments.config_layers.push(format!("database : {}", entry.title));
+            }
+        }
+        // ROM whose platform is not known by database is also detected.
+        let is_profile_known = arguments.rom_entry.as_ref().is_some_and(|entry| entry.profile.is_some());
+        if self.is_detect_used && !is_profile_known {
+            // Layout options of config files and command line decide where code is scanned, so they are resolved first.
+            let mut probe = Arguments { quirks: arguments.quirks, rom_hash: arguments.rom_hash.clone(), ..Arguments::default() };
+            self.apply_layers(&mut probe, file_path)?;
+            let layout = probe.memory_layout();
+            // Unknown ROM falls back to detected platform, only when detection is trustable.
+            let detection = detect::detect(&CodeMap::walk(&rom, layout.load_address, layout.entry_point));
+            if detection.confidence >= Confidence::Medium {
+                arguments.quirks = detection.profile;
+                arguments.config_layers.push(format!("detected : {} ({} confidence)",
+                    detection.profile.name(), detection.confidence.name()));
+            }
+            arguments.detection = Some(detection);
+        }
+        self.apply_layers(&mut arguments, file_path)?;
+        arguments.memory_layout().check()?;
+        arguments.file_path = file_path.to_string();
+        Ok(arguments)
+    }
+
+    /// Apply user config, per-ROM config and command-line options of given ROM file to arguments.
+    fn apply_layers(&self, arguments: &mut Arguments, file_path: &str) -> Result<(), String> {
+        if self.is_config_used {
+            let layers = apply_configs(arguments, file_path, self.config.as_deref())?;
+            arguments.config_layers.extend(layers);
+        }
+        for (name, value) in &self.options {
+            arguments.apply_option(name, value.as_deref())?;
+        }
+        if !self.options.is_empty() {
+            arguments.config_layers.push(String::from("command-line options"));
+        }
+        Ok(())
+    }
+}
+
+/// Parse command-line arguments.
+/// If no ROM file or directory is given to `run` command, ROM browser is selected.
+pub fn parse_command_line(args: &mut env::Args) -> Result<CommandLine, String> {
+    let mut command_line = CommandLine {
+        command: Command::Run,
+        file_path: None,
+        options: Vec::new(),
+        config: None,
+        is_config_used: true,
+        database: None,
+        is_database_used: true,
+        is_detect_used: true,
+        loaded_database: OnceCell::new(),
+    };
+
+    // Skip executable path.
+    args.next();
+    let mut args = args.peekable();
+    match args.peek().map(|arg| &arg[..]) {
+        Some("config") => {
+            args.next();
+            match args.next().as_deref() {
+                Some("show") => command_line.command = Command::ConfigShow,
+                _ => return Err(String::from("Expected `config show`")),
+            }
+        },
+        Some(arg) => if let Some(command) = Command::from_name(arg) {
+            command_line.command = command;
+            args.next();
+        },
+        None => (),
+    }
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--help" | "-h" => command_line.command = Command::Help,
+            "--config" => command_line.config = Some(args.next().ok_or_else(|| String::from("Option `--config` needs value"))?),
+            "--no-config" => command_line.is_config_used = false,
+            "--database" => command_line.database = Some(args.next().ok_or_else(|| String::from("Option `--database` needs value"))?),
+            "--no-database" => command_line.is_database_used = false,
+            "--no-detect" => command_line.is_detect_used = false,
+            _ if arg.starts_with("--") => {
+                // Value can be given as `--option=value` or `--option value`.
+                let option = &arg[2..];
+                let (name, value) = match option.find('=') {
+                    Some(pos) => (&option[..pos], Some(option[pos + 1..].to_string())),
+                    None if FLAG_OPTIONS.contains(&option) => (option, None),
+                    None => (option, args.next()),
+                };
+                command_line.options.push((name.to_string(), value));
+            },
+            _ if command_line.file_path.is_none() => command_line.file_path = Some(arg),
+            _ => return Err(format!("Unexpected argument `{}`", arg)),
+        }
+    }
+
+    // Check options before any ROM is selected.
+    let mut arguments = Arguments::default();
+    for (name, value) in &command_line.options {
+        arguments.apply_option(name, value.as_deref())?;
+    }
+
+    let is_dir = command_line.file_path.as_ref().is_some_and(|path| Path::new(path).is_dir());
+    match (command_line.command, &command_line.file_path) {
+        (Command::Help, _) => (),
+        (Command::Run, None) => command_line.command = Command::Browse,
+        (Command::Run, Some(_)) if is_dir => command_line.command = Command::Browse,
+        (_, Some(_)) => (),
+        (_, None) => return Err(String::from("CHIP-8 file path is not given")),
+    }
+    Ok(command_line)
 }
 
+/// Check ROM file of given path exists and can be read, and validate it with given quirk profile and memory layout.
+/// Found validation issues are returned, and `Err` is returned only if ROM file could not be read.
+pub fn check_rom(path: &str, profile: QuirkProfile, layout: &MemoryLayout) -> Result<Vec<Issue>, String> {
+    if !Path::new(path).is_file() {
+        return Err(format!("`{}` is not valid CHIP-8 file", path));
+    }
+    let rom = fs::read(path).map_err(|err| format!("Could not read `{}` : {}", path, err))?;
+    Ok(validate(&rom, profile, layout))
+}
@@ -0,0 +1,303 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use super::check::Arguments;
+
+/// Provides one setting of config file. Array value has multiple values.
+type Setting = (String, Vec<String>);
+
+/// Provides parsed TOML config file.
+///
+/// Keys are the same as command-line option names without `--`. Tables except `[rom.*]` only group keys.
+/// `[rom."<file name>"]` and `[rom.<sha1 of ROM>]` tables are applied only to the matched ROM.
+/// Supported values are strings, numbers, booleans and single-line arrays of them.
+pub struct ConfigFile {
+    pub path: String,
+    /// Settings out of ROM tables.
+    global: Vec<Setting>,
+    /// Settings of ROM tables, keyed by file name or SHA-1 hash.
+    roms: Vec<(String, Vec<Setting>)>,
+}
+
+impl ConfigFile {
+    /// Load config file of given path.
+    pub fn load(path: &str) -> Result<ConfigFile, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("Could not read config `{}` : {}", path, err))?;
+        ConfigFile::parse(path, &text)
+    }
+
+    /// Parse given TOML text. `path` is used for error message.
+    pub fn parse(path: &str, text: &str) -> Result<ConfigFile, String> {
+        let mut config = ConfigFile { path: path.to_string(), global: Vec::new(), roms: Vec::new() };
+        // Index of ROM table which following keys belong to.
+        let mut rom_table: Option<usize> = None;
+
+        for (line_no, line) in text.lines().enumerate() {
+            let error = |msg: &str| format!("{} : line {} : {}", path, line_no + 1, msg);
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // Table header.
+            if line.starts_with('[') {
+                let header = line.strip_prefix('[').and_then(|line| line.strip_suffix(']'))
+                    .ok_or_else(|| error("expected `[table]`"))?
+                    .trim();
+                rom_table = match header.split_once('.') {
+                    Some((table, key)) if table.trim() == "rom" => {
+                        let key = unquote(key.trim()).ok_or_else(|| error("invalid ROM table key"))?;
+                        config.roms.push((key, Vec::new()));
+                        Some(config.roms.len() - 1)
+                    },
+                    _ => None,
+                };
+                continue;
+            }
+
+            // Key and value.
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected `key = value`"))?;
+            let key = unquote(key.trim()).ok_or_else(|| error("invalid key"))?;
+            let values = parse_values(value.trim()).ok_or_else(|| error("invalid value"))?;
+            match rom_table {
+                Some(index) => config.roms[index].1.push((key, values)),
+                None => config.global.push((key, values)),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Get settings of ROM table which matches given ROM file name or SHA-1 hash.
+    fn rom_settings(&self, file_name: &str, hash: &str) -> Option<(&str, &[Setting])> {
+        self.roms.iter()
+            .find(|(key, _)| key == file_name || key.eq_ignore_ascii_case(hash))
+            .map(|(key, settings)| (&key[..], &settings[..]))
+    }
+}
+
+/// Apply settings into arguments.
+fn apply_settings(arguments: &mut Arguments, settings: &[Setting], path: &str) -> Result<(), String> {
+    for (key, values) in settings {
+        for value in values {
+            arguments.apply_option(key, Some(value)).map_err(|err| format!("{} : {}", path, err))?;
+        }
+    }
+    Ok(())
+}
+
+/// Get path of user config file.
+/// `CHIPMUNK_CONFIG` environment variable, `$XDG_CONFIG_HOME/chipmunk/config.toml`
+/// or `$HOME/.config/chipmunk/config.toml` is used in order.
+pub fn user_config_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("CHIPMUNK_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = env::var("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| Path::new(&home).join(".config")))
+        .ok()?;
+    Some(config_home.join("chipmunk").join("config.toml"))
+}
+
+/// Apply config layers of ROM into arguments, as user config < ROM table of user config < `<rom>.toml`.
+/// If `user_config` path is given, it must exist. Otherwise default user config is used if it exists.
+/// Return descriptions of applied layers.
+pub fn apply_configs(arguments: &mut Arguments, rom_path: &str, user_config: Option<&str>)
+    -> Result<Vec<String>, String> {
+    let mut layers = Vec::new();
+    let user_config = match user_config {
+        Some(path) => Some(ConfigFile::load(path)?),
+        None => match user_config_path() {
+            Some(path) if path.is_file() => Some(ConfigFile::load(&path.to_string_lossy())?),
+            _ => None,
+        },
+    };
+
+    if let Some(config) = &user_config {
+        apply_settings(arguments, &config.global, &config.path)?;
+        layers.push(config.path.clone());
+
+        let file_name = Path::new(rom_path).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let hash = arguments.rom_hash.clone();
+        if let Some((key, settings)) = config.rom_settings(&file_name, &hash) {
+            apply_settings(arguments, settings, &config.path)?;
+            layers.push(format!("{} [rom.\"{}\"]", config.path, key));
+        }
+    }
+
+    // ROM config file next to ROM. Every key is applied to the ROM.
+    let rom_config = format!("{}.toml", rom_path);
+    if Path::new(&rom_config).is_file() {
+        let config = ConfigFile::load(&rom_config)?;
+        apply_settings(arguments, &config.global, &config.path)?;
+        layers.push(rom_config);
+    }
+    Ok(layers)
+}
+
+/// Remove comment which starts with `#` out of strings.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut is_escaped = false;
+    for (index, chr) in line.char_indices() {
+        match (quote, chr) {
+            (Some('"'), '\\') if !is_escaped => { is_escaped = true; continue; },
+            (Some(q), _) if chr == q && !is_escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(chr),
+            (None, '#') => return &line[..index],
+            _ => (),
+        }
+        is_escaped = false;
+    }
+    line
+}
+
+/// Get content of quoted string or bare key.
+fn unquote(text: &str) -> Option<String> {
+    match parse_string(text) {
+        Some((value, rest)) if rest.trim().is_empty() => Some(value),
+        Some(_) => None,
+        None if !text.is_empty() && text.chars().all(|chr| chr.is_ascii_alphanumeric() || chr == '-' || chr == '_') => {
+            Some(text.to_string())
+        },
+        None => None,
+    }
+}
+
+/// Parse quoted string at the beginning of text, and return the string and the rest text.
+fn parse_string(text: &str) -> Option<(String, &str)> {
+    let quote = text.chars().next().filter(|&chr| chr == '"' || chr == '\'')?;
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1);
+    while let Some((index, chr)) = chars.next() {
+        match chr {
+            _ if chr == quote => return Some((value, &text[index + 1..])),
+            '\\' if quote == '"' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                escaped => value.push(escaped),
+            },
+            _ => value.push(chr),
+        }
+    }
+    None
+}
+
+/// Parse value or array of values as strings.
+fn parse_values(text: &str) -> Option<Vec<String>> {
+    if let Some(inner) = text.strip_prefix('[') {
+        let inner = inner.strip_suffix(']')?;
+        let mut values = Vec::new();
+        let mut rest = inner.trim();
+        while !rest.is_empty() {
+            let (value, next) = parse_scalar(rest)?;
+            values.push(value);
+            rest = next.trim_start();
+            rest = match rest.strip_prefix(',') {
+                Some(next) => next.trim_start(),
+                None if rest.is_empty() => rest,
+                None => return None,
+            };
+        }
+        return Some(values);
+    }
+
+    match parse_scalar(text)? {
+        (value, rest) if rest.trim().is_empty() => Some(vec![value]),
+        _ => None,
+    }
+}
+
+/// Parse string, number or boolean at the beginning of text, and return it with the rest text.
+fn parse_scalar(text: &str) -> Option<(String, &str)> {
+    if let Some(result) = parse_string(text) {
+        return Some(result);
+    }
+    let end = text.find(|chr: char| chr == ',' || chr.is_whitespace()).unwrap_or(text.len());
+    let token = &text[..end];
+    let is_valid = token == "true" || token == "false" || token.parse::<f64>().is_ok();
+    if is_valid { Some((token.to_string(), &text[end..])) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(key: &str, values: &[&str]) -> Setting {
+        (key.to_string(), values.iter().map(|value| value.to_string()).collect())
+    }
+
+    #[test]
+    fn parse_quoted_strings() {
+        let config = ConfigFile::parse("test", concat!(
+            "theme = \"lcd\"\n",
+            "keymap = 'C:\\keys\\dvorak.txt'\n",
+            "title = \"say \\\"hi\\\"\\tnow\"\n",
+        )).unwrap();
+        assert_eq!(config.global, vec![
+            setting("theme", &["lcd"]),
+            setting("keymap", &["C:\\keys\\dvorak.txt"]),
+            setting("title", &["say \"hi\"\tnow"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_comments() {
+        let config = ConfigFile::parse("test", concat!(
+            "# whole line comment\n",
+            "\n",
+            "ipf = 15  # trailing comment\n",
+            "theme = \"#33FF33\" # hash in string is kept\n",
+            "keymap = 'a#b'\n",
+        )).unwrap();
+        assert_eq!(config.global, vec![
+            setting("ipf", &["15"]),
+            setting("theme", &["#33FF33"]),
+            setting("keymap", &["a#b"]),
+        ]);
+    }
+
+    #[test]
+    fn parse_arrays() {
+        let config = ConfigFile::parse("test", concat!(
+            "key = [\"1=x\", '2=c', 3, true]\n",
+            "empty = []\n",
+            "trailing = [ 1.5 , 2 , ]\n",
+        )).unwrap();
+        assert_eq!(config.global, vec![
+            setting("key", &["1=x", "2=c", "3", "true"]),
+            setting("empty", &[]),
+            setting("trailing", &["1.5", "2"]),
+        ]);
+        assert!(ConfigFile::parse("test", "key = [1 2]").is_err());
+        assert!(ConfigFile::parse("test", "key = [\"open\"").is_err());
+    }
+
+    #[test]
+    fn parse_rom_tables() {
+        let config = ConfigFile::parse("test", concat!(
+            "ipf = 10\n",
+            "[display]\n",
+            "theme = \"green\"\n",
+            "[rom.\"Maze [David Winter, 199x].ch8\"]\n",
+            "ipf = 30\n",
+            "[rom.0123456789ABCDEF0123456789ABCDEF01234567]\n",
+            "quirks = \"schip\"\n",
+        )).unwrap();
+        assert_eq!(config.global, vec![setting("ipf", &["10"]), setting("theme", &["green"])]);
+        assert_eq!(config.rom_settings("Maze [David Winter, 199x].ch8", ""),
+            Some(("Maze [David Winter, 199x].ch8", &[setting("ipf", &["30"])][..])));
+        assert_eq!(config.rom_settings("other.ch8", "0123456789abcdef0123456789abcdef01234567").map(|(_, settings)| settings),
+            Some(&[setting("quirks", &["schip"])][..]));
+        assert_eq!(config.rom_settings("other.ch8", "da39a3ee5e6b4b0d3255bfef95601890afd80709"), None);
+    }
+
+    #[test]
+    fn parse_errors_with_line() {
+        let err = ConfigFile::parse("config.toml", "ipf = 10\nipf 10\n").err().unwrap();
+        assert_eq!(err, "config.toml : line 2 : expected `key = value`");
+        assert!(ConfigFile::parse("test", "[rom.\"open]").is_err());
+        assert!(ConfigFile::parse("test", "theme = green").is_err());
+    }
+}
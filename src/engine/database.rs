@@ -0,0 +1,151 @@
+use std::{env, fs, path::{Path, PathBuf}};
+
+use super::json::Value;
+use super::quirks::{QuirkProfile, Quirks};
+
+/// Bundled program list of well-known ROMs, in community CHIP-8 database format.
+const BUNDLED_PROGRAMS: &str = include_str!("../../assets/database/programs.json");
+/// Bundled map from SHA-1 hash of ROM into index of bundled program list.
+const BUNDLED_HASHES: &str = include_str!("../../assets/database/sha1-hashes.json");
+
+/// Provides known information of one ROM in database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomEntry {
+    pub title: String,
+    pub authors: Vec<String>,
+    /// Release year or date as written in database.
+    pub release: Option<String>,
+    pub description: Option<String>,
+    /// Target platform names as written in database. (e.g. `originalChip8`, `superchip`, `xochip`)
+    pub platforms: Vec<String>,
+    /// Quirk profile of the first target platform. `None` if no profile matches the platform.
+    pub profile: Option<QuirkProfile>,
+    /// Quirks which differ from the quirk profile, if required.
+    pub quirks: Option<Quirks>,
+    /// Recommended instructions per frame.
+    pub tickrate: Option<u32>,
+    /// Keypad hints as (host key name, CHIP-8 key) pairs.
+    pub keys: Vec<(String, u8)>,
+}
+
+/// Provides ROM database of community CHIP-8 database format.
+///
+/// It consists of `programs.json` which is list of programs with their ROMs keyed by SHA-1 hash,
+/// and `sha1-hashes.json` which maps SHA-1 hash into index of program list.
+pub struct Database {
+    programs: Value,
+    hashes: Value,
+}
+
+impl Database {
+    /// Get database which is bundled into executable.
+    pub fn bundled() -> Result<Database, String> {
+        Database::parse(BUNDLED_PROGRAMS, BUNDLED_HASHES).map_err(|err| format!("Bundled database : {}", err))
+    }
+
+    /// Load database from directory which has `programs.json` and `sha1-hashes.json`.
+    pub fn load(dir: &str) -> Result<Database, String> {
+        let read = |name: &str| {
+            let path = Path::new(dir).join(name);
+            fs::read_to_string(&path).map_err(|err| format!("Could not read `{}` : {}", path.display(), err))
+        };
+        Database::parse(&read("programs.json")?, &read("sha1-hashes.json")?)
+            .map_err(|err| format!("{} : {}", dir, err))
+    }
+
+    fn parse(programs: &str, hashes: &str) -> Result<Database, String> {
+        Ok(Database { programs: Value::parse(programs)?, hashes: Value::parse(hashes)? })
+    }
+
+    /// Find ROM of given SHA-1 hash (lowercase hexadecimal).
+    pub fn lookup(&self, hash: &str) -> Option<RomEntry> {
+        let index = self.hashes.get(hash)?.as_f64()? as usize;
+        let program = self.programs.as_array().get(index)?;
+        let rom = program.get("roms")?.get(hash)?;
+
+        let platforms: Vec<String> = rom.get("platforms").map(Value::as_array).unwrap_or_default()
+            .iter()
+            .filter_map(|platform| platform.as_str().map(String::from))
+            .collect();
+        let profile = platforms.first().and_then(|name| platform_profile(name));
+        // Quirks of the target platform itself are given by `quirkyPlatforms`.
+        let quirks = platforms.first()
+            .and_then(|name| Some((profile?, rom.get("quirkyPlatforms")?.get(name)?)))
+            .map(|(profile, overrides)| apply_quirk_overrides(profile.quirks(), overrides));
+        let keys = rom.get("keys").map(Value::as_object).unwrap_or_default()
+            .iter()
+            .filter_map(|(name, key)| Some((host_key_name(name)?.to_string(), key.as_f64()? as u8)))
+            .filter(|&(_, key)| key < 16)
+            .collect();
+
+        Some(RomEntry {
+            title: program.get("title").and_then(Value::as_str).unwrap_or("Unknown").to_string(),
+            authors: program.get("authors").map(Value::as_array).unwrap_or_default()
+                .iter()
+                .filter_map(|author| author.as_str().map(String::from))
+                .collect(),
+            release: program.get("release").and_then(Value::as_str).map(String::from),
+            description: program.get("description").and_then(Value::as_str).map(String::from),
+            platforms,
+            profile,
+            quirks,
+            tickrate: rom.get("tickrate").and_then(Value::as_f64).map(|rate| rate as u32),
+            keys,
+        })
+    }
+}
+
+/// Get directory of installed database, which is used instead of bundled one when `--database` is not given.
+/// `CHIPMUNK_DATABASE` environment variable, `$XDG_DATA_HOME/chipmunk/database`
+/// or `$HOME/.local/share/chipmunk/database` is used in order.
+pub fn default_dir() -> Option<PathBuf> {
+    if let Ok(path) = env::var("CHIPMUNK_DATABASE") {
+        return Some(PathBuf::from(path));
+    }
+    let data_home = env::var("XDG_DATA_HOME").map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+        .ok()?;
+    Some(data_home.join("chipmunk").join("database"))
+}
+
+/// Get quirk profile of platform id of community database.
+/// `None` is returned for platform which does not match any profile, such as `modernChip8`.
+fn platform_profile(platform: &str) -> Option<QuirkProfile> {
+    match platform {
+        "originalChip8" | "hybridVIP" | "chip8x" => Some(QuirkProfile::Cosmac),
+        "chip48" | "superchip1" | "superchip" | "megachip8" => Some(QuirkProfile::Schip),
+        "xochip" => Some(QuirkProfile::XoChip),
+        _ => None,
+    }
+}
+
+/// Apply quirk flags of community database into given quirks. Unsupported flags are ignored.
+fn apply_quirk_overrides(mut quirks: Quirks, overrides: &Value) -> Quirks {
+    for (name, value) in overrides.as_object() {
+        let value = match value.as_bool() {
+            Some(value) => value,
+            None => continue,
+        };
+        match &name[..] {
+            "shift" => quirks.shift_vx = value,
+            "memoryLeaveIUnchanged" => quirks.load_store_increment = !value,
+            "jump" => quirks.jump_vx = value,
+            "logic" => quirks.vf_reset = value,
+            _ => (),
+        }
+    }
+    quirks
+}
+
+/// Get host key name of keypad hint name of community database.
+fn host_key_name(name: &str) -> Option<&'static str> {
+    match name {
+        "up" => Some("Up"),
+        "down" => Some("Down"),
+        "left" => Some("Left"),
+        "right" => Some("Right"),
+        "a" => Some("Space"),
+        "b" => Some("Enter"),
+        _ => None,
+    }
+}
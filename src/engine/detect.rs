@@ -0,0 +1,163 @@
+use super::analysis::CodeMap;
+use super::quirks::QuirkProfile;
+
+/// Provides how much detected platform can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// Only weak quirk patterns, or nothing is found.
+    Low,
+    /// One kind of platform specific opcode, or consistent quirk patterns are found.
+    Medium,
+    /// Several platform specific opcodes are found.
+    High,
+}
+
+impl Confidence {
+    /// Get canonical name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+}
+
+/// Provides platform which is proposed by scanning reachable code of ROM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub profile: QuirkProfile,
+    pub confidence: Confidence,
+    /// Found evidences with their first address.
+    pub reasons: Vec<String>,
+}
+
+/// Get XO-CHIP specific opcode name of given word.
+fn xochip_opcode(word: u16) -> Option<&'static str> {
+    match word {
+        0xF000 => Some("F000 long I load"),
+        0xF002 => Some("F002 audio pattern"),
+        _ if word & 0xF00F == 0x5002 => Some("5XY2 save range"),
+        _ if word & 0xF00F == 0x5003 => Some("5XY3 load range"),
+        _ if word & 0xF0FF == 0xF001 => Some("FN01 plane select"),
+        _ if word & 0xF0FF == 0xF03A => Some("FX3A pitch"),
+        _ if word & 0xFFF0 == 0x00D0 => Some("00DN scroll up"),
+        _ => None,
+    }
+}
+
+/// Get SUPER-CHIP specific opcode name of given word.
+fn schip_opcode(word: u16) -> Option<&'static str> {
+    match word {
+        0x00FF => Some("00FF high resolution"),
+        0x00FE => Some("00FE low resolution"),
+        0x00FB | 0x00FC => Some("00FB / 00FC scroll"),
+        0x00FD => Some("00FD exit"),
+        _ if word & 0xFFF0 == 0x00C0 => Some("00CN scroll down"),
+        _ if word & 0xF00F == 0xD000 => Some("DXY0 16x16 sprite"),
+        _ if word & 0xF0FF == 0xF075 || word & 0xF0FF == 0xF085 => Some("FX75 / FX85 flags"),
+        _ if word & 0xF0FF == 0xF030 => Some("FX30 large font"),
+        _ => None,
+    }
+}
+
+/// Get name of SUPER-CHIP / XO-CHIP specific opcode of given word, which is not implemented by this emulator.
+///
+/// Only XO-CHIP `FN01` plane select, `F002` audio pattern and `FX3A` pitch are implemented.
+pub fn unsupported_opcode(word: u16) -> Option<&'static str> {
+    match word {
+        0xF002 => None,
+        _ if word & 0xF0FF == 0xF001 && (word >> 8) & 0xF <= 3 => None,
+        _ if word & 0xF0FF == 0xF03A => None,
+        _ => xochip_opcode(word).or_else(|| schip_opcode(word)),
+    }
+}
+
+/// Provides collected evidences of one platform.
+#[derive(Default)]
+struct Evidence {
+    /// (name, first address) of each found kind.
+    kinds: Vec<(&'static str, u16)>,
+    count: usize,
+}
+
+impl Evidence {
+    fn add(&mut self, name: &'static str, address: u16) {
+        self.count += 1;
+        if !self.kinds.iter().any(|&(kind, _)| kind == name) {
+            self.kinds.push((name, address));
+        }
+    }
+
+    fn reasons(&self) -> Vec<String> {
+        self.kinds.iter().map(|(name, address)| format!("{} at 0x{:03X}", name, address)).collect()
+    }
+
+    /// Get confidence of opcode evidence.
+    fn opcode_confidence(&self) -> Confidence {
+        if self.kinds.len() >= 2 || self.count >= 3 { Confidence::High } else { Confidence::Medium }
+    }
+}
+
+/// Detect platform of ROM by scanning reachable code.
+///
+/// XO-CHIP and SUPER-CHIP specific opcodes decide platform directly. Otherwise quirk patterns are voted;
+/// 8XY6 / 8XYE with X != Y and FX55 / FX65 followed by another FX55 / FX65 rely on COSMAC behavior,
+/// FX55 / FX65 followed by reuse of I and BNNN after setting VX rely on SUPER-CHIP behavior.
+pub fn detect(code: &CodeMap) -> Detection {
+    let mut xochip = Evidence::default();
+    let mut schip = Evidence::default();
+    let mut cosmac_quirks = Evidence::default();
+    let mut schip_quirks = Evidence::default();
+
+    for (index, &(address, word)) in code.code.iter().enumerate() {
+        if let Some(name) = xochip_opcode(word) {
+            xochip.add(name, address);
+        }
+        if let Some(name) = schip_opcode(word) {
+            schip.add(name, address);
+        }
+
+        let (x, y) = ((word >> 8) & 0xF, (word >> 4) & 0xF);
+        match word & 0xF00F {
+            0x8006 | 0x800E if x != y => cosmac_quirks.add("8XY6 / 8XYE with X != Y", address),
+            _ => (),
+        }
+        if word & 0xF0FF == 0xF055 || word & 0xF0FF == 0xF065 {
+            match code.next_use_of_i(index) {
+                Some(next) if next & 0xF0FF == 0xF055 || next & 0xF0FF == 0xF065 => {
+                    cosmac_quirks.add("FX55 / FX65 followed by FX55 / FX65", address);
+                },
+                Some(_) => schip_quirks.add("FX55 / FX65 followed by reuse of I", address),
+                None => (),
+            }
+        }
+        if word >> 12 == 0xB {
+            // Register which is set just before BNNN is likely to be offset of jump.
+            let previous = index.checked_sub(1).map(|index| code.code[index])
+                .filter(|&(previous, _)| previous.checked_add(2) == Some(address));
+            match previous.map(|(_, word)| word) {
+                Some(set) if set >> 12 == 0x6 || set & 0xF00F == 0x8000 => match (set >> 8) & 0xF {
+                    0 => cosmac_quirks.add("BNNN after setting V0", address),
+                    r if r == x => schip_quirks.add("BXNN after setting VX", address),
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+    }
+
+    let (profile, confidence, reasons) = if !xochip.kinds.is_empty() {
+        (QuirkProfile::XoChip, xochip.opcode_confidence(), xochip.reasons())
+    } else if !schip.kinds.is_empty() {
+        (QuirkProfile::Schip, schip.opcode_confidence(), schip.reasons())
+    } else if schip_quirks.count > cosmac_quirks.count {
+        let confidence = if schip_quirks.count >= cosmac_quirks.count + 3 { Confidence::Medium } else { Confidence::Low };
+        (QuirkProfile::Schip, confidence, schip_quirks.reasons())
+    } else if cosmac_quirks.count > 0 {
+        (QuirkProfile::Cosmac, Confidence::Medium, cosmac_quirks.reasons())
+    } else {
+        (QuirkProfile::Cosmac, Confidence::Low, vec![String::from("no platform specific opcode")])
+    };
+    Detection { profile, confidence, reasons }
+}
@@ -6,14 +6,21 @@ use std::{
 
 extern crate crossterm;
 use crossterm::{
-    cursor, style,
+    cursor, style, event,
     terminal::{self, ClearType},
 };
 
+use super::cast::CastRecorder;
+
 /// Provides rendering device.
 /// To use device, valid terminal or console must be provided from OS.
+///
+/// All commands are queued into frame buffer, and written to terminal at once by `present()`.
 pub struct Device {
-    stdout: io::Stdout
+    stdout: io::Stdout,
+    buffer: Vec<u8>,
+    /// Recorder which receives the same bytes written to terminal.
+    tee: Option<CastRecorder>,
 }
 
 impl Drop for Device {
@@ -23,27 +30,41 @@ impl Drop for Device {
             self.stdout,    // stdout will be moved into closure.
             style::ResetColor,
             cursor::Show,
+            event::DisableMouseCapture,
             terminal::LeaveAlternateScreen);
         let _ = terminal::disable_raw_mode();
+        if let Some(tee) = self.tee.as_mut() {
+            let _ = tee.flush();
+        }
     }
 }
 
 impl Device {
-    /// Create new device instance. 
+    /// Create new device instance.
     pub fn new() -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
         // Create new local stdout and setup alternative screen.
         let mut stdout = io::stdout();
-        crossterm::execute!(stdout, terminal::EnterAlternateScreen)?;
+        crossterm::execute!(stdout, terminal::EnterAlternateScreen, event::EnableMouseCapture)?;
         crossterm::terminal::enable_raw_mode()?;
 
         // Set value into stdout of struct.
-        Ok(Device { stdout })
+        Ok(Device { stdout, buffer: Vec::with_capacity(16 << 10), tee: None })
+    }
+
+    /// Get terminal size as (columns, rows).
+    pub fn size(&self) -> Result<(u16, u16), crossterm::ErrorKind> {
+        terminal::size()
+    }
+
+    /// Set recorder which receives all bytes written to terminal from now.
+    pub fn set_tee(&mut self, tee: CastRecorder) {
+        self.tee = Some(tee);
     }
 
     /// Clear screen.
     pub fn clear(&mut self) -> Result<(), crossterm::ErrorKind> {
         // Clear screen.
-        crossterm::execute!(&mut self.stdout,
+        crossterm::queue!(&mut self.buffer,
             crossterm::style::ResetColor,
             terminal::Clear(ClearType::All),
             cursor::Hide,
@@ -51,14 +72,57 @@ impl Device {
         Ok(())
     }
 
+    /// Move cursor to given `pos`.
+    pub fn move_to(&mut self, pos: (u16, u16)) -> Result<(), crossterm::ErrorKind> {
+        crossterm::queue!(&mut self.buffer, cursor::MoveTo(pos.0, pos.1))?;
+        Ok(())
+    }
+
+    /// Set foreground and background color of following prints.
+    pub fn set_colors(&mut self, fg: style::Color, bg: style::Color) -> Result<(), crossterm::ErrorKind> {
+        crossterm::queue!(&mut self.buffer, style::SetColors(style::Colors::new(fg, bg)))?;
+        Ok(())
+    }
+
+    /// Reset foreground and background color into terminal default color.
+    pub fn reset_color(&mut self) -> Result<(), crossterm::ErrorKind> {
+        crossterm::queue!(&mut self.buffer, style::ResetColor)?;
+        Ok(())
+    }
+
+    /// Print given `string` at the current cursor position.
+    pub fn print(&mut self, string: &str) -> Result<(), crossterm::ErrorKind> {
+        self.buffer.extend_from_slice(string.as_bytes());
+        Ok(())
+    }
+
     /// Move cursor to given `pos` and print given `string`.
-    pub fn mv_print(&mut self, pos: (u8, u8), string: &str) -> Result<(), crossterm::ErrorKind> {
-        let (x, y) = (pos.0 as u16, pos.1 as u16);
-        crossterm::execute!(
-            &mut self.stdout, 
-            cursor::MoveTo(x, y), 
-            style::Print(string)
+    /// If `is_inversed` is true, string is printed with reversed foreground and background color.
+    pub fn mv_print_attr(&mut self, pos: (u16, u16), string: &str, is_inversed: bool)
+        -> Result<(), crossterm::ErrorKind> {
+        let attr = if is_inversed { style::Attribute::Reverse } else { style::Attribute::NoReverse };
+        crossterm::queue!(
+            &mut self.buffer,
+            cursor::MoveTo(pos.0, pos.1),
+            style::SetAttribute(attr),
+            style::Print(string),
+            style::SetAttribute(style::Attribute::NoReverse)
         )?;
         Ok(())
     }
+
+    /// Write all queued commands into terminal with one write, and flush.
+    /// Return written byte count.
+    pub fn present(&mut self) -> io::Result<usize> {
+        let written = self.buffer.len();
+        if written > 0 {
+            self.stdout.write_all(&self.buffer)?;
+            self.stdout.flush()?;
+            if let Some(tee) = self.tee.as_mut() {
+                tee.write(&self.buffer)?;
+            }
+            self.buffer.clear();
+        }
+        Ok(written)
+    }
 }
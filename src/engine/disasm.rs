@@ -0,0 +1,69 @@
+use std::io::{self, Write};
+
+use super::isa::{self, Instruction};
+
+/// Get assembly mnemonic of given instruction. `word` is raw 2 bytes of instruction.
+pub fn mnemonic(instruction: &Instruction, word: u16) -> String {
+    type Inst = Instruction;
+    match *instruction {
+        Inst::Ignore => format!("SYS 0x{:03X}", word & 0x0FFF),
+        Inst::ClearDisplay => String::from("CLS"),
+        Inst::ReturnSubroutine => String::from("RET"),
+        Inst::JmpAddr(addr) => format!("JP 0x{:03X}", addr),
+        Inst::CallSub(addr) => format!("CALL 0x{:03X}", addr),
+        Inst::SkipEq{ r, val } => format!("SE V{:X}, 0x{:02X}", r, val),
+        Inst::SkipNeq{ r, val } => format!("SNE V{:X}, 0x{:02X}", r, val),
+        Inst::SkipRegEq{ r, f } => format!("SE V{:X}, V{:X}", r, f),
+        Inst::SetByte{ r, val } => format!("LD V{:X}, 0x{:02X}", r, val),
+        Inst::AddByte{ r, val } => format!("ADD V{:X}, 0x{:02X}", r, val),
+        Inst::SetRegV{ r, f } => format!("LD V{:X}, V{:X}", r, f),
+        Inst::OrRegV{ r, f } => format!("OR V{:X}, V{:X}", r, f),
+        Inst::AndRegV{ r, f } => format!("AND V{:X}, V{:X}", r, f),
+        Inst::XorRegV{ r, f } => format!("XOR V{:X}, V{:X}", r, f),
+        Inst::AddRegV{ r, f } => format!("ADD V{:X}, V{:X}", r, f),
+        Inst::SubRegV{ r, f } => format!("SUB V{:X}, V{:X}", r, f),
+        Inst::ShrRegV{ r, f } => format!("SHR V{:X}, V{:X}", r, f),
+        Inst::SubNRegV{ r, f } => format!("SUBN V{:X}, V{:X}", r, f),
+        Inst::ShlRegV{ r, f } => format!("SHL V{:X}, V{:X}", r, f),
+        Inst::SkipRegNeq{ r, f } => format!("SNE V{:X}, V{:X}", r, f),
+        Inst::SetRegL(addr) => format!("LD I, 0x{:03X}", addr),
+        Inst::JmpAddrOffReg0(addr) => format!("JP V0, 0x{:03X}", addr),
+        Inst::RndAnd{ r, val } => format!("RND V{:X}, 0x{:02X}", r, val),
+        Inst::DispSpr{ rp, n } => format!("DRW V{:X}, V{:X}, {}", rp.0, rp.1, n),
+        Inst::SkipKeyPressed{ r } => format!("SKP V{:X}", r),
+        Inst::SkipKeyReleased{ r } => format!("SKNP V{:X}", r),
+        Inst::SetDelayToReg{ r } => format!("LD V{:X}, DT", r),
+        Inst::SelectPlanes{ n } => format!("PLANE {}", n),
+        Inst::LoadAudioPattern => String::from("AUDIO"),
+        Inst::WaitKeyPress{ r } => format!("LD V{:X}, K", r),
+        Inst::SetDelayFromReg{ r } => format!("LD DT, V{:X}", r),
+        Inst::SetSoundFromReg{ r } => format!("LD ST, V{:X}", r),
+        Inst::AddRegL{ r } => format!("ADD I, V{:X}", r),
+        Inst::SetRegLFontAddrFromReg{ r } => format!("LD F, V{:X}", r),
+        Inst::MemDumpBcdFromReg{ r } => format!("LD B, V{:X}", r),
+        Inst::SetPitchFromReg{ r } => format!("PITCH V{:X}", r),
+        Inst::MemDump{ endr } => format!("LD [I], V{:X}", endr),
+        Inst::MemRead{ endr } => format!("LD V{:X}, [I]", endr),
+    }
+}
+
+/// Write disassembly listing of given ROM bytes which is loaded at `base` address.
+/// Each line has address, raw word, bit field and mnemonic. Undecodable word is written as data.
+pub fn write_listing<W: Write>(mut w: W, rom: &[u8], base: u16) -> io::Result<()> {
+    for (index, chunk) in rom.chunks(2).enumerate() {
+        let address = base as usize + index * 2;
+        if chunk.len() < 2 {
+            writeln!(w, "0x{:03X}  {:02X}    {:<17}  DB 0x{:02X}", address, chunk[0], "", chunk[0])?;
+            continue;
+        }
+
+        let bytes = [chunk[0], chunk[1]];
+        let word = u16::from_be_bytes(bytes);
+        let text = match isa::parse_instruction(&bytes) {
+            Some(instruction) => mnemonic(&instruction, word),
+            None => format!("DW 0x{:04X}", word),
+        };
+        writeln!(w, "0x{:03X}  {:04X}  {}  {}", address, word, isa::to_bitfield_string(&bytes, '1', '0'), text)?;
+    }
+    Ok(())
+}
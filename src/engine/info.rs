@@ -0,0 +1,255 @@
+use super::analysis::CodeMap;
+use super::database::RomEntry;
+use super::detect::{self, Detection};
+use super::isa;
+use super::json::Value;
+use super::layout::MemoryLayout;
+use super::quirks::QuirkProfile;
+use super::romname::RomName;
+use super::validate::{self, Issue};
+
+/// Provides output format of ROM information report.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReportFormat {
+    /// Aligned lines for human.
+    #[default]
+    Text,
+    /// JSON object for other tools.
+    Json,
+}
+
+impl ReportFormat {
+    /// Get report format from given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<ReportFormat> {
+        match &name.to_lowercase()[..] {
+            "text" => Some(ReportFormat::Text),
+            "json" => Some(ReportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Provides information report of ROM, which is used to triage ROM packs.
+pub struct InfoReport {
+    pub path: String,
+    pub size: usize,
+    pub sha1: String,
+    /// Metadata parsed from file name and sidecar description file.
+    pub name: RomName,
+    /// Memory layout where ROM is loaded.
+    pub layout: MemoryLayout,
+    /// Quirk profile which is used to run ROM.
+    pub profile: QuirkProfile,
+    pub rom_entry: Option<RomEntry>,
+    pub detection: Option<Detection>,
+    /// SUPER-CHIP / XO-CHIP opcodes which are not implemented in reachable code, with their first address.
+    pub unsupported: Vec<String>,
+    /// Count of reachable words.
+    pub reachable: usize,
+    /// Instruction variant names with their counts in reachable code, in descending order of count.
+    pub histogram: Vec<(&'static str, usize)>,
+    /// Count of undecodable words in reachable code.
+    pub undecodable: usize,
+    /// Maximum depth of nested subroutine calls. `None` if recursive call is found.
+    pub call_depth: Option<usize>,
+    /// Sprite data regions as [start, end) addresses.
+    pub sprite_regions: Vec<(u16, u16)>,
+    pub reads_keys: bool,
+    pub uses_sound: bool,
+    pub uses_random: bool,
+    pub issues: Vec<Issue>,
+}
+
+impl InfoReport {
+    /// Create new report of given ROM bytes and its SHA-1 hash, which is run with quirk profile and memory layout.
+    pub fn new(path: &str, rom: &[u8], sha1: &str, profile: QuirkProfile, layout: &MemoryLayout, rom_entry: Option<&RomEntry>,
+        detection: Option<&Detection>) -> InfoReport {
+        let code = CodeMap::walk(rom, layout.load_address, layout.entry_point);
+
+        let mut histogram: Vec<(&'static str, usize)> = Vec::new();
+        let mut undecodable = 0;
+        let mut unsupported: Vec<String> = Vec::new();
+        for &(address, word) in &code.code {
+            if let Some(name) = detect::unsupported_opcode(word) {
+                if !unsupported.iter().any(|reason| reason.starts_with(name)) {
+                    unsupported.push(format!("{} at 0x{:03X}", name, address));
+                }
+            }
+            let name = match isa::parse_instruction(&word.to_be_bytes()) {
+                Some(instruction) => instruction.name(),
+                None => { undecodable += 1; continue; },
+            };
+            match histogram.iter_mut().find(|(variant, _)| *variant == name) {
+                Some((_, count)) => *count += 1,
+                None => histogram.push((name, 1)),
+            }
+        }
+        histogram.sort_by(|lhs, rhs| rhs.1.cmp(&lhs.1).then(lhs.0.cmp(rhs.0)));
+        let is_used = |names: &[&str]| histogram.iter().any(|(name, _)| names.contains(name));
+
+        InfoReport {
+            path: path.to_string(),
+            size: rom.len(),
+            sha1: sha1.to_string(),
+            name: RomName::from_path(path),
+            layout: layout.clone(),
+            profile,
+            rom_entry: rom_entry.cloned(),
+            detection: detection.cloned(),
+            unsupported,
+            reachable: code.code.len(),
+            undecodable,
+            call_depth: code.call_depth(rom, layout.entry_point),
+            sprite_regions: code.sprite_regions(),
+            reads_keys: is_used(&["SkipKeyPressed", "SkipKeyReleased", "WaitKeyPress"]),
+            uses_sound: is_used(&["SetSoundFromReg", "LoadAudioPattern", "SetPitchFromReg"]),
+            uses_random: is_used(&["RndAnd"]),
+            histogram,
+            issues: validate::validate(rom, profile, layout),
+        }
+    }
+
+    /// Get report as aligned text lines.
+    pub fn to_text(&self) -> String {
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+        let mut lines = vec![
+            format!("File              : {}", self.path),
+            format!("Size              : {} bytes ({} words)", self.size, self.size.div_ceil(2)),
+            format!("SHA-1             : {}", self.sha1),
+            format!("Quirk profile     : {}", self.profile.name()),
+            format!("Name title        : {}", self.name.title),
+        ];
+        if let Some(author) = &self.name.author {
+            lines.push(format!("Name author       : {}", author));
+        }
+        if let Some(year) = &self.name.year {
+            lines.push(format!("Name year         : {}", year));
+        }
+        if !self.name.tags.is_empty() {
+            lines.push(format!("Name tags         : {}", self.name.tags.join(", ")));
+        }
+        if let Some(description) = &self.name.description {
+            lines.push(String::from("Description file  :"));
+            lines.extend(description.lines().map(|line| format!("    {}", line)));
+        }
+        if let Some(detection) = &self.detection {
+            lines.push(format!("Detected platform : {} ({} confidence)", detection.profile.name(), detection.confidence.name()));
+            lines.extend(detection.reasons.iter().map(|reason| format!("                    {}", reason)));
+        }
+        match self.unsupported.is_empty() {
+            true => lines.push(String::from("Platform support  : supported")),
+            false => {
+                lines.push(String::from("Platform support  : unsupported platform"));
+                lines.extend(self.unsupported.iter().map(|reason| format!("                    {}", reason)));
+            },
+        }
+        match &self.rom_entry {
+            Some(entry) => {
+                lines.push(format!("Title             : {}", entry.title));
+                lines.push(format!("Authors           : {}", entry.authors.join(", ")));
+                lines.push(format!("Release           : {}", entry.release.as_deref().unwrap_or("-")));
+                lines.push(format!("Platforms         : {}", entry.platforms.join(", ")));
+                if let Some(tickrate) = entry.tickrate {
+                    lines.push(format!("Speed             : {} instructions per frame", tickrate));
+                }
+                if let Some(description) = &entry.description {
+                    lines.push(format!("Description       : {}", description));
+                }
+            },
+            None => lines.push(String::from("Database          : not found")),
+        }
+
+        lines.push(format!("Memory layout     : {}", self.layout.summary()));
+        lines.push(format!("Reachable words   : {} ({} undecodable)", self.reachable, self.undecodable));
+        lines.push(format!("Call depth        : {}", match self.call_depth {
+            Some(depth) => depth.to_string(),
+            None => String::from("recursive"),
+        }));
+        lines.push(format!("Sprite regions    : {}", match self.sprite_regions.is_empty() {
+            true => String::from("-"),
+            false => self.sprite_regions.iter()
+                .map(|(start, end)| format!("0x{:03X}-0x{:03X}", start, end - 1))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }));
+        lines.push(format!("Reads keys        : {}", yes_no(self.reads_keys)));
+        lines.push(format!("Uses sound        : {}", yes_no(self.uses_sound)));
+        lines.push(format!("Uses RndAnd       : {}", yes_no(self.uses_random)));
+
+        lines.push(String::from("Opcode histogram  :"));
+        lines.extend(self.histogram.iter().map(|(name, count)| format!("    {:<24} {:>5}", name, count)));
+        lines.push(format!("Issues            : {}", self.issues.len()));
+        lines.extend(self.issues.iter().map(|issue| format!("    {}", issue)));
+        lines.join("\n")
+    }
+
+    /// Get report as JSON object.
+    pub fn to_json(&self) -> Value {
+        let string = |text: &str| Value::String(text.to_string());
+        let number = |value: usize| Value::Number(value as f64);
+        let strings = |texts: &[String]| Value::Array(texts.iter().map(|text| string(text)).collect());
+        let optional = |text: &Option<String>| text.as_deref().map(string).unwrap_or(Value::Null);
+
+        let database = match &self.rom_entry {
+            Some(entry) => Value::Object(vec![
+                (String::from("title"), string(&entry.title)),
+                (String::from("authors"), strings(&entry.authors)),
+                (String::from("release"), optional(&entry.release)),
+                (String::from("description"), optional(&entry.description)),
+                (String::from("platforms"), strings(&entry.platforms)),
+                (String::from("tickrate"), entry.tickrate.map(|rate| number(rate as usize)).unwrap_or(Value::Null)),
+            ]),
+            None => Value::Null,
+        };
+        let detection = match &self.detection {
+            Some(detection) => Value::Object(vec![
+                (String::from("platform"), string(detection.profile.name())),
+                (String::from("confidence"), string(detection.confidence.name())),
+                (String::from("reasons"), strings(&detection.reasons)),
+            ]),
+            None => Value::Null,
+        };
+        let name = Value::Object(vec![
+            (String::from("title"), string(&self.name.title)),
+            (String::from("author"), optional(&self.name.author)),
+            (String::from("year"), optional(&self.name.year)),
+            (String::from("tags"), strings(&self.name.tags)),
+            (String::from("description"), optional(&self.name.description)),
+        ]);
+        let issues = self.issues.iter()
+            .map(|issue| Value::Object(vec![
+                (String::from("severity"), string(issue.severity.name())),
+                (String::from("address"), issue.address.map(|address| number(address as usize)).unwrap_or(Value::Null)),
+                (String::from("message"), string(&issue.message)),
+            ]))
+            .collect();
+
+        Value::Object(vec![
+            (String::from("file"), string(&self.path)),
+            (String::from("size"), number(self.size)),
+            (String::from("sha1"), string(&self.sha1)),
+            (String::from("quirks"), string(self.profile.name())),
+            (String::from("name"), name),
+            (String::from("detection"), detection),
+            (String::from("unsupported_opcodes"), strings(&self.unsupported)),
+            (String::from("database"), database),
+            (String::from("load_address"), number(self.layout.load_address as usize)),
+            (String::from("entry"), number(self.layout.entry_point as usize)),
+            (String::from("font_address"), number(self.layout.font_address as usize)),
+            (String::from("memory_size"), number(self.layout.memory_size)),
+            (String::from("reachable_words"), number(self.reachable)),
+            (String::from("undecodable_words"), number(self.undecodable)),
+            (String::from("call_depth"), self.call_depth.map(number).unwrap_or(Value::Null)),
+            (String::from("sprite_regions"), Value::Array(self.sprite_regions.iter()
+                .map(|&(start, end)| Value::Array(vec![number(start as usize), number(end as usize)]))
+                .collect())),
+            (String::from("reads_keys"), Value::Bool(self.reads_keys)),
+            (String::from("uses_sound"), Value::Bool(self.uses_sound)),
+            (String::from("uses_random"), Value::Bool(self.uses_random)),
+            (String::from("histogram"), Value::Object(self.histogram.iter()
+                .map(|&(name, count)| (name.to_string(), number(count)))
+                .collect())),
+            (String::from("issues"), Value::Array(issues)),
+        ])
+    }
+}
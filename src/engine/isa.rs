@@ -29,16 +29,66 @@ pub enum Instruction {
     SkipKeyPressed{ r: u8 },        // 0xEx9E Skip next instruction if VX value key is pressed.
     SkipKeyReleased{ r: u8 },       // 0xExA1 Skip next instruction if VX value key is not pressed.
     SetDelayToReg{ r: u8 },         // 0xFx07 Store the current value of the delay timer to VX.
+    SelectPlanes{ n: u8 },          // 0xFn01 (XO-CHIP) Select planes of n bits to draw and clear.
+    LoadAudioPattern,               // 0xF002 (XO-CHIP) Load 16-byte audio pattern from [l, l+15].
     WaitKeyPress{ r: u8 },          // 0xFx0A Wait for key press. Pressed key value stored to VX.
     SetDelayFromReg{ r: u8 },       // 0xFx15 Set the delay timer to the value of register VX.
     SetSoundFromReg{ r: u8 },       // 0xFx18 Set the sound timer to the value of register VX.
     AddRegL{ r: u8 },               // 0xFx1E ADD l, Vx. l += Vx.
     SetRegLFontAddrFromReg{ r: u8 },// 0xFx29 Set L to the memory addr from sprite value from VX.
     MemDumpBcdFromReg{ r: u8 },     // 0xFx33 Store BCD from value of VX at address [L, max L+2].
+    SetPitchFromReg{ r: u8 },       // 0xFx3A (XO-CHIP) Set audio pattern playback pitch to VX.
     MemDump{ endr: u8 },            // 0xFx55 LD [l], Vx. Store [V0, Vx] value from [l, l+(x-0)].
     MemRead{ endr: u8 },            // 0xFx65 LD Vx, [l]. Read value from [l, l+(x-0)] to [V0, Vx].
 }
 
+impl Instruction {
+    /// Get variant name of instruction, which is used for statistics.
+    pub fn name(&self) -> &'static str {
+        type Inst = Instruction;
+        match self {
+            Inst::Ignore => "Ignore",
+            Inst::ClearDisplay => "ClearDisplay",
+            Inst::ReturnSubroutine => "ReturnSubroutine",
+            Inst::JmpAddr(..) => "JmpAddr",
+            Inst::CallSub(..) => "CallSub",
+            Inst::SkipEq{ .. } => "SkipEq",
+            Inst::SkipNeq{ .. } => "SkipNeq",
+            Inst::SkipRegEq{ .. } => "SkipRegEq",
+            Inst::SetByte{ .. } => "SetByte",
+            Inst::AddByte{ .. } => "AddByte",
+            Inst::SetRegV{ .. } => "SetRegV",
+            Inst::OrRegV{ .. } => "OrRegV",
+            Inst::AndRegV{ .. } => "AndRegV",
+            Inst::XorRegV{ .. } => "XorRegV",
+            Inst::AddRegV{ .. } => "AddRegV",
+            Inst::SubRegV{ .. } => "SubRegV",
+            Inst::ShrRegV{ .. } => "ShrRegV",
+            Inst::SubNRegV{ .. } => "SubNRegV",
+            Inst::ShlRegV{ .. } => "ShlRegV",
+            Inst::SkipRegNeq{ .. } => "SkipRegNeq",
+            Inst::SetRegL(..) => "SetRegL",
+            Inst::JmpAddrOffReg0(..) => "JmpAddrOffReg0",
+            Inst::RndAnd{ .. } => "RndAnd",
+            Inst::DispSpr{ .. } => "DispSpr",
+            Inst::SkipKeyPressed{ .. } => "SkipKeyPressed",
+            Inst::SkipKeyReleased{ .. } => "SkipKeyReleased",
+            Inst::SetDelayToReg{ .. } => "SetDelayToReg",
+            Inst::SelectPlanes{ .. } => "SelectPlanes",
+            Inst::LoadAudioPattern => "LoadAudioPattern",
+            Inst::WaitKeyPress{ .. } => "WaitKeyPress",
+            Inst::SetDelayFromReg{ .. } => "SetDelayFromReg",
+            Inst::SetSoundFromReg{ .. } => "SetSoundFromReg",
+            Inst::AddRegL{ .. } => "AddRegL",
+            Inst::SetRegLFontAddrFromReg{ .. } => "SetRegLFontAddrFromReg",
+            Inst::MemDumpBcdFromReg{ .. } => "MemDumpBcdFromReg",
+            Inst::SetPitchFromReg{ .. } => "SetPitchFromReg",
+            Inst::MemDump{ .. } => "MemDump",
+            Inst::MemRead{ .. } => "MemRead",
+        }
+    }
+}
+
 fn get_12bit_from(bytes: &[u8; 2]) -> u16 {
     (((bytes[0] & 0x0F) as u16) << 8) + bytes[1] as u16
 }
@@ -96,6 +146,8 @@ pub fn parse_instruction(bytes: &[u8; 2]) -> Option<Instruction> {
         },
         0xF => {
             match bytes[1] {
+                0x01 if r <= 3 => Some(Instruction::SelectPlanes{ n: r }),
+                0x02 if r == 0 => Some(Instruction::LoadAudioPattern),
                 0x07 => Some(Instruction::SetDelayToReg{ r }),
                 0x0A => Some(Instruction::WaitKeyPress{ r }),
                 0x15 => Some(Instruction::SetDelayFromReg{ r }),
@@ -103,6 +155,7 @@ pub fn parse_instruction(bytes: &[u8; 2]) -> Option<Instruction> {
                 0x1E => Some(Instruction::AddRegL{ r }),
                 0x29 => Some(Instruction::SetRegLFontAddrFromReg{ r }),
                 0x33 => Some(Instruction::MemDumpBcdFromReg{ r }),
+                0x3A => Some(Instruction::SetPitchFromReg{ r }),
                 0x55 => Some(Instruction::MemDump{ endr: r }),
                 0x65 => Some(Instruction::MemRead{ endr: r }),
                 _ => None,
@@ -119,7 +172,7 @@ pub fn to_bitfield_string(bytes: &[u8; 2], true_char: char, false_char: char) ->
 
     for item in bytes {
         for i in (0..8).rev() {
-            if (*item & ((0b1 as u8) << i)) != 0x00 {
+            if (*item & (0b1_u8 << i)) != 0x00 {
                 result.push(true_char);
             } else {
                 result.push(false_char);
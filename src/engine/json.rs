@@ -0,0 +1,331 @@
+/// Provides parsed JSON value.
+/// Object keeps the order of members as written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Parse given JSON text.
+    pub fn parse(text: &str) -> Result<Value, String> {
+        let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        match parser.pos < parser.chars.len() {
+            true => Err(parser.error("unexpected trailing characters")),
+            false => Ok(value),
+        }
+    }
+
+    /// Get member of object with given key.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Value] {
+        match self {
+            Value::Array(values) => values,
+            _ => &[],
+        }
+    }
+
+    pub fn as_object(&self) -> &[(String, Value)] {
+        match self {
+            Value::Object(members) => members,
+            _ => &[],
+        }
+    }
+}
+
+impl Value {
+    /// Get JSON text of value, which is indented with 2 spaces.
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out
+    }
+
+    fn write_pretty(&self, out: &mut String, depth: usize) {
+        let indent = |out: &mut String, depth: usize| out.push_str(&"  ".repeat(depth));
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(value) => out.push_str(&value.to_string()),
+            Value::Number(value) => out.push_str(&value.to_string()),
+            Value::String(value) => write_string(out, value),
+            Value::Array(values) if values.is_empty() => out.push_str("[]"),
+            Value::Object(members) if members.is_empty() => out.push_str("{}"),
+            Value::Array(values) => {
+                out.push_str("[\n");
+                for (index, value) in values.iter().enumerate() {
+                    indent(out, depth + 1);
+                    value.write_pretty(out, depth + 1);
+                    out.push_str(if index + 1 < values.len() { ",\n" } else { "\n" });
+                }
+                indent(out, depth);
+                out.push(']');
+            },
+            Value::Object(members) => {
+                out.push_str("{\n");
+                for (index, (name, value)) in members.iter().enumerate() {
+                    indent(out, depth + 1);
+                    write_string(out, name);
+                    out.push_str(": ");
+                    value.write_pretty(out, depth + 1);
+                    out.push_str(if index + 1 < members.len() { ",\n" } else { "\n" });
+                }
+                indent(out, depth);
+                out.push('}');
+            },
+        }
+    }
+}
+
+/// Write given text as JSON string literal with quotes.
+fn write_string(out: &mut String, text: &str) {
+    out.push('"');
+    for chr in text.chars() {
+        match chr {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ if (chr as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", chr as u32)),
+            _ => out.push(chr),
+        }
+    }
+    out.push('"');
+}
+
+/// Provides recursive descent parser of JSON text.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> String {
+        format!("JSON : {} at character {}", msg, self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, chr: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(next) if next == chr => { self.pos += 1; Ok(()) },
+            _ => Err(self.error(&format!("expected `{}`", chr))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Value::String),
+            Some('t') => self.literal("true", Value::Bool(true)),
+            Some('f') => self.literal("false", Value::Bool(false)),
+            Some('n') => self.literal("null", Value::Null),
+            Some(chr) if chr == '-' || chr.is_ascii_digit() => self.number(),
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        let end = self.pos + word.len();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(word.chars()) {
+            self.pos = end;
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(chr) = self.peek() {
+            if !(chr.is_ascii_digit() || "+-.eE".contains(chr)) {
+                break;
+            }
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map(Value::Number).map_err(|_| self.error("invalid number"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let chr = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match chr {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => value.push('\n'),
+                        'r' => value.push('\r'),
+                        't' => value.push('\t'),
+                        'b' => value.push('\u{8}'),
+                        'f' => value.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // Character out of BMP is escaped as surrogate pair.
+                            let is_pair = self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u'][..]);
+                            if (0xD800..0xDC00).contains(&code) && is_pair {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code = match low {
+                                    0xDC00..=0xDFFF => 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00),
+                                    _ => { value.push('\u{FFFD}'); low },
+                                };
+                            }
+                            value.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        },
+                        _ => value.push(escaped),
+                    }
+                },
+                _ => value.push(chr),
+            }
+        }
+    }
+
+    /// Parse 4 hexadecimal digits of `\u` escape.
+    fn hex4(&mut self) -> Result<u32, String> {
+        let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+        if hex.len() < 4 {
+            return Err(self.error("invalid escape"));
+        }
+        let code = u32::from_str_radix(&hex, 16).map_err(|_| self.error("invalid escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => { self.pos += 1; return Ok(Value::Array(values)); },
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => { self.pos += 1; return Ok(Value::Object(members)); },
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_string(text: &str) -> String {
+        Value::parse(text).unwrap().as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_escapes() {
+        assert_eq!(parse_string(r#""a\"b\\c\/d""#), "a\"b\\c/d");
+        assert_eq!(parse_string(r#""\n\r\t\b\f""#), "\n\r\t\u{8}\u{c}");
+        assert_eq!(parse_string(r#""\u0041\u00e9\u2588""#), "Aé█");
+        assert!(Value::parse(r#""\u00G1""#).is_err());
+        assert!(Value::parse(r#""\u00"#).is_err());
+        assert!(Value::parse(r#""open"#).is_err());
+    }
+
+    #[test]
+    fn parse_surrogates() {
+        assert_eq!(parse_string(r#""\ud83d\ude00""#), "😀");
+        assert_eq!(parse_string(r#""\uD834\uDD1E!""#), "𝄞!");
+        // Unpaired surrogates are replaced.
+        assert_eq!(parse_string(r#""\ud83dx""#), "\u{FFFD}x");
+        assert_eq!(parse_string(r#""\ude00""#), "\u{FFFD}");
+        assert_eq!(parse_string(r#""\ud83d\u0041""#), "\u{FFFD}A");
+    }
+
+    #[test]
+    fn parse_document() {
+        let value = Value::parse(r#"{"title": "Maze", "tickrate": 30, "tags": ["a", "b"], "ok": true, "none": null}"#).unwrap();
+        assert_eq!(value.get("title").and_then(Value::as_str), Some("Maze"));
+        assert_eq!(value.get("tickrate").and_then(Value::as_f64), Some(30.0));
+        assert_eq!(value.get("tags").map(|tags| tags.as_array().len()), Some(2));
+        assert_eq!(value.get("ok").and_then(Value::as_bool), Some(true));
+        assert_eq!(value.get("none"), Some(&Value::Null));
+        assert!(Value::parse("[1, 2").is_err());
+    }
+
+    #[test]
+    fn write_escapes() {
+        let value = Value::String(String::from("\"\\\n\r\t\u{1b}[0m é"));
+        assert_eq!(value.to_pretty_string(), r#""\"\\\n\r\t\u001b[0m é""#);
+        assert_eq!(parse_string(&value.to_pretty_string()), "\"\\\n\r\t\u{1b}[0m é");
+    }
+}
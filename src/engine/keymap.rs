@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+};
+
+extern crate crossterm;
+use crossterm::event::KeyCode;
+
+/// CHIP-8 key values laid out as COSMAC VIP hex keypad, from top row to bottom row.
+/// Positional presets bind their host keys to this layout, and on-screen keypad is drawn in this layout.
+pub(crate) const COSMAC_LAYOUT: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+/// Provides built-in keyboard layout presets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    Qwerty,
+    Azerty,
+    Qwertz,
+    Dvorak,
+    NumpadHex,
+}
+
+impl Preset {
+    /// Get preset from given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<Preset> {
+        match &name.to_lowercase()[..] {
+            "qwerty" => Some(Preset::Qwerty),
+            "azerty" => Some(Preset::Azerty),
+            "qwertz" => Some(Preset::Qwertz),
+            "dvorak" => Some(Preset::Dvorak),
+            "numpad-hex" | "hex" => Some(Preset::NumpadHex),
+            _ => None,
+        }
+    }
+
+    /// Get host key rows of positional preset which are matched to `COSMAC_LAYOUT`.
+    /// Each row can have several alternative strings, all of them are bound.
+    fn rows(self) -> &'static [&'static [&'static str; 4]] {
+        match self {
+            Preset::Qwerty => &[&["1", "2", "3", "4"], &["q", "w", "e", "r"],
+                                &["a", "s", "d", "f"], &["z", "x", "c", "v"]],
+            // AZERTY top row gives symbols without shift, so bind both of them.
+            Preset::Azerty => &[&["&", "é", "\"", "'"], &["1", "2", "3", "4"],
+                                &["a", "z", "e", "r"], &["q", "s", "d", "f"],
+                                &["w", "x", "c", "v"]],
+            Preset::Qwertz => &[&["1", "2", "3", "4"], &["q", "w", "e", "r"],
+                                &["a", "s", "d", "f"], &["y", "x", "c", "v"]],
+            Preset::Dvorak => &[&["1", "2", "3", "4"], &["'", ",", ".", "p"],
+                                &["a", "o", "e", "u"], &[";", "q", "j", "k"]],
+            Preset::NumpadHex => &[],
+        }
+    }
+}
+
+/// Provides mapping from host keyboard keys into CHIP-8 keypad keys.
+pub struct Keymap {
+    map: HashMap<KeyCode, u8>,
+}
+
+impl Keymap {
+    /// Create new keymap from given preset.
+    pub fn from_preset(preset: Preset) -> Keymap {
+        let mut keymap = Keymap { map: HashMap::new() };
+        keymap.load_preset(preset);
+        keymap
+    }
+
+    /// Remove all bindings and bind keys of given preset.
+    pub fn load_preset(&mut self, preset: Preset) {
+        self.map.clear();
+
+        if preset == Preset::NumpadHex {
+            // Hex digits are bound to the same key value,
+            // and numpad operators are bound to A ~ F.
+            for key in 0x0u8..=0xFu8 {
+                let chr = std::char::from_digit(key as u32, 16).unwrap();
+                self.map.insert(KeyCode::Char(chr), key);
+            }
+            for (chr, key) in ['/', '*', '-', '+', '.'].iter().zip(0xAu8..) {
+                self.map.insert(KeyCode::Char(*chr), key);
+            }
+            self.map.insert(KeyCode::Enter, 0xF);
+            return;
+        }
+
+        let rows = preset.rows();
+        // Rows which exceed layout rows are alternatives of leading rows. (e.g. AZERTY)
+        let extra_rows = rows.len() - COSMAC_LAYOUT.len();
+        for (i, row) in rows.iter().enumerate() {
+            let layout_row = if i < extra_rows { i } else { i - extra_rows };
+            for (name, &key) in row.iter().zip(COSMAC_LAYOUT[layout_row].iter()) {
+                if let Some(code) = parse_host_key(name) {
+                    self.map.insert(code, key);
+                }
+            }
+        }
+    }
+
+    /// Apply keymap description from given `text`.
+    ///
+    /// Each line is `<host key> = <chip-8 key>` or `preset = <preset name>`.
+    /// `preset` line removes all previous bindings. Text after `#` is ignored.
+    pub fn apply_str(&mut self, text: &str) -> Result<(), String> {
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (lhs, rhs) = match line.rfind('=') {
+                Some(pos) => (line[..pos].trim(), line[pos + 1..].trim()),
+                None => return Err(format!("line {} : expected `key = value`", line_no + 1)),
+            };
+            if lhs == "preset" {
+                match Preset::from_name(rhs) {
+                    Some(preset) => self.load_preset(preset),
+                    None => return Err(format!("line {} : unknown preset `{}`", line_no + 1, rhs)),
+                }
+            } else {
+                self.bind_str(lhs, rhs).map_err(|err| format!("line {} : {}", line_no + 1, err))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply keymap from given preset name or keymap file path.
+    pub fn apply_source(&mut self, source: &str) -> Result<(), String> {
+        if let Some(preset) = Preset::from_name(source) {
+            self.load_preset(preset);
+            return Ok(());
+        }
+
+        match fs::read_to_string(source) {
+            Ok(text) => self.apply_str(&text).map_err(|err| format!("{} : {}", source, err)),
+            Err(err) => Err(format!("Could not read keymap `{}` : {}", source, err)),
+        }
+    }
+
+    /// Apply per-ROM keymap file if exists.
+    /// Per-ROM keymap file is located at `<rom file path>.keymap`.
+    pub fn apply_rom_override(&mut self, rom_path: &str) -> Result<(), String> {
+        let path = format!("{}.keymap", rom_path);
+        if Path::new(&path).is_file() {
+            self.apply_source(&path)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Bind host key name `host` into CHIP-8 hex key string `key`.
+    pub fn bind_str(&mut self, host: &str, key: &str) -> Result<(), String> {
+        let code = match parse_host_key(host) {
+            Some(code) => code,
+            None => return Err(format!("unknown host key `{}`", host)),
+        };
+        let key = match u8::from_str_radix(key.strip_prefix("0x").unwrap_or(key), 16) {
+            Ok(key) if key <= 0xF => key,
+            _ => return Err(format!("invalid CHIP-8 key `{}`", key)),
+        };
+
+        self.map.insert(code, key);
+        Ok(())
+    }
+
+    /// Get bound CHIP-8 key from given host key code.
+    pub fn key_of(&self, code: KeyCode) -> Option<u8> {
+        let code = match code {
+            KeyCode::Char(chr) => KeyCode::Char(chr.to_lowercase().next().unwrap_or(chr)),
+            code => code,
+        };
+        self.map.get(&code).copied()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::from_preset(Preset::Qwerty)
+    }
+}
+
+/// Parse host key name into key code.
+/// Single character is matched to character key, otherwise named keys are matched.
+fn parse_host_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(chr), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(chr.to_lowercase().next().unwrap_or(chr)));
+    }
+
+    match &name.to_lowercase()[..] {
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "enter" => Some(KeyCode::Enter),
+        "space" => Some(KeyCode::Char(' ')),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "insert" => Some(KeyCode::Insert),
+        "delete" => Some(KeyCode::Delete),
+        "home" => Some(KeyCode::Home),
+        "end" => Some(KeyCode::End),
+        "pageup" => Some(KeyCode::PageUp),
+        "pagedown" => Some(KeyCode::PageDown),
+        "hash" => Some(KeyCode::Char('#')),
+        "equals" => Some(KeyCode::Char('=')),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qwerty_preset_matches_cosmac_layout() {
+        let keymap = Keymap::from_preset(Preset::Qwerty);
+        assert_eq!(keymap.key_of(KeyCode::Char('1')), Some(0x1));
+        assert_eq!(keymap.key_of(KeyCode::Char('4')), Some(0xC));
+        assert_eq!(keymap.key_of(KeyCode::Char('x')), Some(0x0));
+        assert_eq!(keymap.key_of(KeyCode::Char('V')), Some(0xF));
+        assert_eq!(keymap.key_of(KeyCode::Char('5')), None);
+    }
+
+    #[test]
+    fn azerty_preset_binds_alternative_rows() {
+        let keymap = Keymap::from_preset(Preset::Azerty);
+        assert_eq!(keymap.key_of(KeyCode::Char('&')), Some(0x1));
+        assert_eq!(keymap.key_of(KeyCode::Char('1')), Some(0x1));
+        assert_eq!(keymap.key_of(KeyCode::Char('w')), Some(0xA));
+    }
+
+    #[test]
+    fn bind_hex_keys() {
+        let mut keymap = Keymap::from_preset(Preset::Qwerty);
+        keymap.bind_str("up", "0x5").unwrap();
+        keymap.bind_str("space", "b").unwrap();
+        assert_eq!(keymap.key_of(KeyCode::Up), Some(0x5));
+        assert_eq!(keymap.key_of(KeyCode::Char(' ')), Some(0xB));
+        assert!(keymap.bind_str("up", "0x10").is_err());
+        assert!(keymap.bind_str("up", "0x0x5").is_err());
+        assert!(keymap.bind_str("no-such-key", "1").is_err());
+    }
+
+    #[test]
+    fn apply_keymap_text() {
+        let mut keymap = Keymap::from_preset(Preset::Qwerty);
+        keymap.apply_str("preset = numpad-hex # reset\n\nhash = 0xE\n").unwrap();
+        assert_eq!(keymap.key_of(KeyCode::Char('q')), None);
+        assert_eq!(keymap.key_of(KeyCode::Char('+')), Some(0xD));
+        assert_eq!(keymap.key_of(KeyCode::Enter), Some(0xF));
+        assert_eq!(keymap.key_of(KeyCode::Char('#')), Some(0xE));
+        assert_eq!(keymap.apply_str("q 1"), Err(String::from("line 1 : expected `key = value`")));
+        assert_eq!(keymap.apply_str("preset = colemak"), Err(String::from("line 1 : unknown preset `colemak`")));
+    }
+}
@@ -0,0 +1,120 @@
+use super::quirks::QuirkProfile;
+
+/// Size of font data, 16 characters of 5 bytes.
+pub const FONT_SIZE: usize = 16 * 5;
+/// The largest memory size, which 16-bit address can reach. (XO-CHIP)
+pub const MAX_MEMORY_SIZE: usize = 64 << 10;
+
+/// Provides memory layout of platform, where ROM and font are placed in memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryLayout {
+    /// Address where ROM is loaded.
+    pub load_address: u16,
+    /// Initial value of program counter.
+    pub entry_point: u16,
+    /// Address of font data which `FX29` points.
+    pub font_address: u16,
+    /// Size of whole memory.
+    pub memory_size: usize,
+    /// Regions as [start, end) addresses which are used by interpreter, so ROM should not overlap them.
+    pub reserved: Vec<(usize, usize)>,
+}
+
+impl Default for MemoryLayout {
+    /// Common layout of CHIP-8 and SUPER-CHIP, which loads ROM at 0x200 of 4KiB memory.
+    fn default() -> Self {
+        MemoryLayout {
+            load_address: 0x200,
+            entry_point: 0x200,
+            font_address: 0x000,
+            memory_size: 4 << 10,
+            reserved: vec![(0x000, 0x200)],
+        }
+    }
+}
+
+impl MemoryLayout {
+    /// Get memory layout preset of given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<MemoryLayout> {
+        let default = MemoryLayout::default();
+        match &name.to_lowercase()[..] {
+            "default" | "chip8" | "chip-8" | "schip" => Some(default),
+            // COSMAC VIP uses the top of memory as stack, variables and display buffer.
+            "vip" | "cosmac" => Some(MemoryLayout { reserved: vec![(0x000, 0x200), (0xEA0, 0x1000)], ..default }),
+            // ETI-660 loads ROM at 0x600.
+            "eti660" | "eti-660" => Some(MemoryLayout {
+                load_address: 0x600,
+                entry_point: 0x600,
+                reserved: vec![(0x000, 0x600)],
+                ..default
+            }),
+            "xochip" | "xo-chip" => Some(MemoryLayout { memory_size: MAX_MEMORY_SIZE, ..default }),
+            _ => None,
+        }
+    }
+
+    /// Get memory layout of platform of quirk profile.
+    pub fn of_profile(profile: QuirkProfile) -> MemoryLayout {
+        let name = match profile {
+            QuirkProfile::Cosmac => "vip",
+            QuirkProfile::Schip => "schip",
+            QuirkProfile::XoChip => "xochip",
+        };
+        MemoryLayout::from_name(name).unwrap_or_default()
+    }
+
+    /// Get maximum size of ROM which fits into memory above load address.
+    pub fn max_rom_size(&self) -> usize {
+        self.memory_size.saturating_sub(self.load_address as usize)
+    }
+
+    /// Check addresses and font of layout are in memory.
+    pub fn check(&self) -> Result<(), String> {
+        if self.memory_size < FONT_SIZE || self.memory_size > MAX_MEMORY_SIZE {
+            return Err(format!("Memory size {} is not in {} ~ {}", self.memory_size, FONT_SIZE, MAX_MEMORY_SIZE));
+        }
+        for (name, address) in [("Load address", self.load_address), ("Entry point", self.entry_point)] {
+            if address as usize >= self.memory_size {
+                return Err(format!("{} 0x{:03X} is out of memory of {} bytes", name, address, self.memory_size));
+            }
+        }
+        if self.font_address as usize + FONT_SIZE > self.memory_size {
+            return Err(format!("Font at 0x{:03X} is out of memory of {} bytes", self.font_address, self.memory_size));
+        }
+        Ok(())
+    }
+
+    /// Get reserved regions and font region which overlap ROM image of given size, as [start, end) addresses.
+    pub fn overlapped_regions(&self, rom_size: usize) -> Vec<(usize, usize)> {
+        let rom_start = self.load_address as usize;
+        let rom_end = rom_start + rom_size;
+        let font = (self.font_address as usize, self.font_address as usize + FONT_SIZE);
+        self.reserved.iter()
+            .chain(std::iter::once(&font))
+            .filter(|&&(start, end)| start < rom_end && rom_start < end)
+            .copied()
+            .collect()
+    }
+
+    /// Get layout as one line, such as `load 0x200, entry 0x200, font 0x000, 4096 bytes`.
+    pub fn summary(&self) -> String {
+        format!("load 0x{:03X}, entry 0x{:03X}, font 0x{:03X}, {} bytes",
+            self.load_address, self.entry_point, self.font_address, self.memory_size)
+    }
+}
+
+/// Parse address or size which is decimal, or hexadecimal with `0x` prefix.
+pub fn parse_number(text: &str) -> Option<usize> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Parse reserved region `<start>-<end>` as [start, end) addresses, such as `0xEA0-0x1000`.
+pub fn parse_region(text: &str) -> Option<(usize, usize)> {
+    let (start, end) = text.split_once('-')?;
+    let (start, end) = (parse_number(start)?, parse_number(end)?);
+    if start < end && end <= MAX_MEMORY_SIZE { Some((start, end)) } else { None }
+}
@@ -0,0 +1,197 @@
+use super::register::{Registers, SideEffect, TimerSideEffect};
+use super::memory::Memory;
+use super::screen::Screen;
+use super::keypad::Keypad;
+use super::state::MachineState;
+use super::quirks::{QuirkProfile, Quirks};
+use super::layout::MemoryLayout;
+
+/// Nominal clock frequency of instruction processing.
+pub const CLOCK_HZ: f64 = 1_760_000.0;
+/// Frequency of delay / sound timer, and frame.
+pub const FRAME_HZ: f64 = 60.0;
+
+/// Provides CHIP-8 machine which consists of memory, registers, screen and keypad.
+/// Machine does not know terminal or time, so it can be run without any device. (headless)
+pub struct Machine {
+    pub memory: Memory,
+    pub registers: Registers,
+    pub screen: Screen,
+    pub keypad: Keypad,
+    pub state: MachineState,
+    /// 16 bytes 1-bit audio pattern loaded by F002. (XO-CHIP)
+    pub audio_pattern: Option<[u8; 16]>,
+    /// Quirk profile which registers follow.
+    pub profile: QuirkProfile,
+    /// Memory layout where ROM and font are placed.
+    pub layout: MemoryLayout,
+    /// Seed of random number generator, which is used again when machine is reset.
+    seed: Option<u64>,
+    /// Count of processed instructions.
+    pub instructions: u64,
+}
+
+impl Machine {
+    /// Create new machine which loads given valid CHIP-8 file as memory layout, with quirk profile.
+    /// If `seed` is given, random numbers are generated deterministically.
+    pub fn new(valid_file_path: &str, profile: QuirkProfile, layout: MemoryLayout, seed: Option<u64>) -> Option<Machine> {
+        Some(Machine {
+            memory: Memory::new(valid_file_path, &layout)?,
+            registers: Registers::new(profile.quirks(), &layout, seed),
+            screen: Screen::new(),
+            keypad: Keypad::new(), // Already reseted.
+            state: MachineState::Normal,
+            audio_pattern: None,
+            profile,
+            layout,
+            seed,
+            instructions: 0,
+        })
+    }
+
+    /// Reload given valid CHIP-8 file, and reset registers, screen and keypad.
+    /// Quirks and processed instruction count are kept.
+    pub fn reset(&mut self, valid_file_path: &str) -> Result<(), String> {
+        self.memory = Memory::new(valid_file_path, &self.layout)
+            .ok_or_else(|| format!("Could not reload `{}`", valid_file_path))?;
+        self.registers = Registers::new(self.registers.quirks(), &self.layout, self.seed);
+        self.screen = Screen::new();
+        self.keypad.reset_all();
+        self.state = MachineState::Normal;
+        self.audio_pattern = None;
+        Ok(())
+    }
+
+    /// Change quirk profile of machine.
+    pub fn set_profile(&mut self, profile: QuirkProfile) {
+        self.profile = profile;
+        self.registers.set_quirks(profile.quirks());
+    }
+
+    /// Change quirks which differ from quirk profile of machine. (e.g. quirks given by ROM database)
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.registers.set_quirks(quirks);
+    }
+
+    /// Get instruction count which is processed in one frame with nominal clock.
+    pub fn instructions_per_frame() -> u32 {
+        (CLOCK_HZ / FRAME_HZ) as u32
+    }
+
+    /// Press given CHIP-8 key.
+    /// If machine state is waiting for key press, change machine state and process side-effect.
+    pub fn press_key(&mut self, key: u8) {
+        self.keypad.set_press(key);
+        if let MachineState::WaitKeyPress{ r } = self.state {
+            self.registers.set_general_register(r, key);
+            self.state = MachineState::Normal;
+        }
+    }
+
+    /// Process one instruction if machine is not waiting for key press.
+    /// If instruction could not be parsed or could not be processed, `Err` is returned and machine must be halted.
+    pub fn step(&mut self) -> Result<(), String> {
+        if self.state != MachineState::Normal {
+            return Ok(());
+        }
+
+        // Parse instruction and process.
+        let instruction = match self.memory.parse_instruction(self.registers.get_pc()) {
+            Some(instruction) => instruction,
+            None => return Err(format!("Register dump : {}", self.registers)),
+        };
+
+        // Update register with instruction, and process consequential side effects.
+        self.instructions += 1;
+        match self.registers.update_registers(instruction)? {
+            Some(SideEffect::ClearDisplay) => {
+                self.screen.clear();
+            },
+            Some(SideEffect::Draw{ pos, n, l: addr }) => {
+                // Update screen buffer. Window buffer will be updated at frame boundary.
+                // New carry flag value will be returned.
+                // Sprite has `n` rows for each selected plane. (XO-CHIP)
+                let count = n as usize * self.screen.selected_plane_count();
+                let sprite = self.memory.get_data_bytes(addr as usize, count)
+                    .ok_or_else(|| self.out_of_memory(addr))?;
+                let is_any_erased = self.screen.draw(pos, &sprite);
+
+                // Update VF (carry & borrow flag)
+                self.registers.update_vf(is_any_erased);
+            },
+            Some(SideEffect::MemDump{ dump_vals, l }) => {
+                self.memory.store_from(&dump_vals, l).ok_or_else(|| self.out_of_memory(l))?;
+            },
+            Some(SideEffect::MemRead{ count, l }) => {
+                // First, get values from memory [l, l + count)
+                // Second, store from v0 to v0 + (count - 1).
+                let values = self.memory.get_data_bytes(l as usize, count as usize).ok_or_else(|| self.out_of_memory(l))?;
+                self.registers.store_from_v0(&values);
+            },
+            Some(SideEffect::SelectPlanes{ n }) => {
+                self.screen.select_planes(n);
+            },
+            Some(SideEffect::LoadAudioPattern{ l }) => {
+                let mut pattern = [0u8; 16];
+                let bytes = self.memory.get_data_bytes(l as usize, 16).ok_or_else(|| self.out_of_memory(l))?;
+                for (dst, src) in pattern.iter_mut().zip(bytes) {
+                    *dst = src;
+                }
+                self.audio_pattern = Some(pattern);
+            },
+            Some(SideEffect::WaitKeyPress{ r }) => {
+                // Let machine wait for new key press.
+                self.state = MachineState::WaitKeyPress{ r };
+            },
+            Some(SideEffect::CheckKeyPressed{ key }) => {
+                match self.keypad.check_press(key).map_err(|err| self.invalid_key(err))? {
+                    true => self.registers.increase_pc(2),
+                    false => self.registers.increase_pc(1),
+                }
+            },
+            Some(SideEffect::CheckKeyReleased{ key }) => {
+                match self.keypad.check_press(key).map_err(|err| self.invalid_key(err))? {
+                    false => self.registers.increase_pc(2),
+                    true => self.registers.increase_pc(1),
+                }
+            },
+            None => (),
+        }
+
+        Ok(())
+    }
+
+    /// Get halt message of key check with invalid key.
+    fn invalid_key(&self, err: String) -> String {
+        format!("{}. Register dump : {}", err, self.registers)
+    }
+
+    /// Get halt message of memory access out of memory at given address.
+    fn out_of_memory(&self, addr: u16) -> String {
+        format!("Memory access at 0x{:04X} is out of memory. Register dump : {}", addr, self.registers)
+    }
+
+    /// Get loaded audio pattern with playback pitch. (XO-CHIP)
+    /// If F002 is not processed yet, `None` is returned and plain tone should be used.
+    pub fn audio_pattern(&self) -> Option<(&[u8; 16], u8)> {
+        self.audio_pattern.as_ref().map(|pattern| (pattern, self.registers.get_pitch()))
+    }
+
+    /// Process delay / sound timer decreasement.
+    /// Unlike instruction parsing and update, timer must be processed independently.
+    /// Even machine state is being waited for key input, timer will be processed.
+    pub fn update_timers(&mut self) -> TimerSideEffect {
+        self.registers.update_timers()
+    }
+
+    /// Process one frame without any device.
+    /// `instructions` count of instructions are processed, and timers are updated once.
+    /// Keys pressed before this call are kept pressed in this frame.
+    pub fn run_frame(&mut self, instructions: u32) -> Result<TimerSideEffect, String> {
+        for _ in 0..instructions {
+            self.step()?;
+        }
+        self.keypad.reset_all();
+        Ok(self.update_timers())
+    }
+}
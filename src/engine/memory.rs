@@ -1,15 +1,16 @@
 use std::fs;
 use std::io::Read;
 use super::isa;
+use super::layout::MemoryLayout;
 
 pub struct Memory {
     memory: Vec<u8>,
 }
 
 impl Memory {
-    pub fn new(valid_file_path: &str) -> Option<Memory> {
-        let mut memory = Vec::<u8>::new();
-        memory.resize(4 << 10, 0);
+    /// Create new memory which has font and ROM of given valid CHIP-8 file, placed as memory layout.
+    pub fn new(valid_file_path: &str, layout: &MemoryLayout) -> Option<Memory> {
+        let mut memory = vec![0u8; layout.memory_size];
 
         // Set default font data into initial memory.
         let font_pack = 
@@ -33,7 +34,7 @@ impl Memory {
             result.extend_from_slice(&[0xF0,  0x80,  0xF0,  0x80,  0x80, ]); // F
             result
         };
-        for (t, r) in memory.iter_mut().zip(font_pack.iter()) { *t = *r; }
+        for (t, r) in memory.iter_mut().skip(layout.font_address as usize).zip(font_pack.iter()) { *t = *r; }
 
         // Set file memory.
         // Read file.
@@ -53,8 +54,8 @@ impl Memory {
             Err(_) => return None,
         }
 
-        // Copy to 0x512~ of memory (to 4KiB)
-        for (t, r) in memory.iter_mut().skip(0x200).zip(data_buffer.iter_mut()) {
+        // Copy to load address~ of memory. Larger ROM is truncated, so it should be validated before.
+        for (t, r) in memory.iter_mut().skip(layout.load_address as usize).zip(data_buffer.iter_mut()) {
             *t = *r;
         }
 
@@ -62,7 +63,7 @@ impl Memory {
         Some(Memory { memory })
     }
 
-    ///
+    /// Print whole memory as hexadecimal words.
     #[allow(dead_code)]
     pub fn print_memory_dump(&self) {
         enum InstructionState { Left, Right, }
@@ -79,7 +80,7 @@ impl Memory {
 
             // Check instruction
             if check_instruction {
-                if address % 0x20 == 0 { print!("\n{:04} : ", address); }
+                if address.is_multiple_of(0x20) { print!("\n{:04} : ", address); }
 
                 print!("{:02x}{:02x} ", instruction[0], instruction[1]);
                 address += 0x02; // 2 Bytes
@@ -92,9 +93,21 @@ impl Memory {
         println!();
     }
 
+    /// Get whole memory bytes.
+    pub fn bytes(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Overwrite whole memory with given bytes.
+    pub fn load_bytes(&mut self, bytes: &[u8]) {
+        for (t, r) in self.memory.iter_mut().zip(bytes.iter()) {
+            *t = *r;
+        }
+    }
+
     pub fn parse_instruction(&self, addr: u16) -> Option<isa::Instruction> {
         // Check out of range exception.
-        if (addr + 1) as usize >= self.memory.len() { return None; } 
+        if addr as usize + 1 >= self.memory.len() { return None; } 
 
         // Parse instruction.
         let addr = addr as usize;
@@ -103,22 +116,26 @@ impl Memory {
         isa::parse_instruction(&bytes)
     }
 
-    pub fn get_data_bytes(&self, addr: usize, count: usize) -> Vec<u8> {
-        assert!(addr < (4usize << 10));
+    /// Get `count` bytes from given address. Bytes out of memory are not included.
+    /// If address is out of memory, `None` is returned.
+    pub fn get_data_bytes(&self, addr: usize, count: usize) -> Option<Vec<u8>> {
+        if addr >= self.memory.len() { return None; }
 
-        self.memory.iter()
+        Some(self.memory.iter()
             .skip(addr)
             .take(count)
-            .map(|&x| x)
-            .collect()
+            .copied()
+            .collect())
     }
 
-    pub fn store_from(&mut self, dump_vals: &[u8], mut l: u16 ) {
+    /// Store given bytes from given address.
+    /// If any address is out of memory, `None` is returned.
+    pub fn store_from(&mut self, dump_vals: &[u8], mut l: u16 ) -> Option<()> {
         for &val in dump_vals {
-            assert!(l < (4 << 10));
-            self.memory[l as usize] = val;
-            l += 1;
+            *self.memory.get_mut(l as usize)? = val;
+            l = l.wrapping_add(1);
         }
+        Some(())
     }
 }
 
@@ -3,7 +3,34 @@ pub mod register;
 pub mod memory;
 pub mod screen;
 pub mod keypad;
+pub mod keymap;
+pub mod touchpad;
 pub mod state;
 pub mod check;
 pub mod device;
-pub mod timer;
\ No newline at end of file
+pub mod render;
+pub mod theme;
+pub mod timer;
+pub mod machine;
+pub mod screenshot;
+pub mod movie;
+pub mod recorder;
+pub mod cast;
+pub mod sound;
+pub mod beep;
+pub mod quirks;
+pub mod status;
+pub mod disasm;
+pub mod savestate;
+pub mod sha1;
+pub mod config;
+pub mod json;
+pub mod database;
+pub mod analysis;
+pub mod detect;
+pub mod validate;
+pub mod romname;
+pub mod info;
+pub mod browser;
+pub mod watch;
+pub mod layout;
@@ -0,0 +1,71 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+};
+
+/// Provides input movie, which is CHIP-8 keys pressed at each frame.
+///
+/// Movie file is a text file. Each line is `<frame> <pressed keys as hex digits>`,
+/// e.g. `120 5A` presses key 5 and A at frame 120. Frames without any key are omitted.
+/// Text after `#` is ignored.
+pub struct Movie {
+    frames: BTreeMap<u64, u16>,
+}
+
+impl Movie {
+    /// Create new empty movie.
+    pub fn new() -> Movie {
+        Movie { frames: BTreeMap::new() }
+    }
+
+    /// Load movie from given file path.
+    pub fn load(path: &str) -> Result<Movie, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("Could not read movie `{}` : {}", path, err))?;
+        let mut movie = Movie::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = || format!("{} : line {} : expected `<frame> <keys>`", path, line_no + 1);
+            let mut tokens = line.split_whitespace();
+            let frame: u64 = tokens.next().and_then(|frame| frame.parse().ok()).ok_or_else(error)?;
+            for chr in tokens.next().ok_or_else(error)?.chars() {
+                let key = chr.to_digit(16).ok_or_else(error)?;
+                movie.press(frame, key as u8);
+            }
+        }
+        Ok(movie)
+    }
+
+    /// Save movie into given file path.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let write = || -> io::Result<()> {
+            let mut file = io::BufWriter::new(fs::File::create(path)?);
+            writeln!(file, "# chipmunk input movie : <frame> <pressed keys>")?;
+            for (frame, &keys) in &self.frames {
+                let keys: String = (0..16u32)
+                    .filter(|key| keys & (1 << key) != 0)
+                    .map(|key| std::char::from_digit(key, 16).unwrap().to_ascii_uppercase())
+                    .collect();
+                writeln!(file, "{} {}", frame, keys)?;
+            }
+            file.flush()
+        };
+        write().map_err(|err| format!("Could not write movie `{}` : {}", path, err))
+    }
+
+    /// Record given key as pressed at given frame.
+    pub fn press(&mut self, frame: u64, key: u8) {
+        *self.frames.entry(frame).or_insert(0) |= 1 << (key & 0xF);
+    }
+
+    /// Get keys which are pressed at given frame.
+    pub fn keys_at(&self, frame: u64) -> Vec<u8> {
+        let keys = self.frames.get(&frame).copied().unwrap_or(0);
+        (0..16u8).filter(|key| keys & (1 << key) != 0).collect()
+    }
+}
@@ -0,0 +1,74 @@
+/// Provides behavior differences of CHIP-8 platforms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quirks {
+    /// 8XY6 / 8XYE shift VX in place, instead of shifting VY into VX.
+    pub shift_vx: bool,
+    /// FX55 / FX65 increase `I` by X + 1.
+    pub load_store_increment: bool,
+    /// BNNN jumps to NNN + VX where X is the highest nibble of NNN, instead of V0.
+    pub jump_vx: bool,
+    /// 8XY1 / 8XY2 / 8XY3 reset VF into 0.
+    pub vf_reset: bool,
+}
+
+impl Quirks {
+    /// Apply given quirk override, which is quirk name to enable it or quirk name with `no-` prefix to disable it.
+    /// Name is not case-sensitive. If name is unknown, `None` is returned.
+    pub fn apply_override(&mut self, value: &str) -> Option<()> {
+        let value = value.to_lowercase();
+        let (name, enabled) = match value.strip_prefix("no-") {
+            Some(name) => (name, false),
+            None => (&value[..], true),
+        };
+        match name {
+            "shift" => self.shift_vx = enabled,
+            "load-store" => self.load_store_increment = enabled,
+            "jump" => self.jump_vx = enabled,
+            "vf-reset" => self.vf_reset = enabled,
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// Provides quirk profile which is well-known set of quirks.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QuirkProfile {
+    /// Original COSMAC VIP CHIP-8 interpreter.
+    #[default]
+    Cosmac,
+    /// SUPER-CHIP 1.1 on HP48 calculators.
+    Schip,
+    /// XO-CHIP of Octo.
+    XoChip,
+}
+
+impl QuirkProfile {
+    /// Get quirk profile from given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<QuirkProfile> {
+        match &name.to_lowercase()[..] {
+            "cosmac" | "vip" | "chip8" | "chip-8" => Some(QuirkProfile::Cosmac),
+            "schip" | "superchip" | "super-chip" => Some(QuirkProfile::Schip),
+            "xochip" | "xo-chip" | "octo" => Some(QuirkProfile::XoChip),
+            _ => None,
+        }
+    }
+
+    /// Get canonical name of quirk profile.
+    pub fn name(self) -> &'static str {
+        match self {
+            QuirkProfile::Cosmac => "cosmac",
+            QuirkProfile::Schip => "schip",
+            QuirkProfile::XoChip => "xochip",
+        }
+    }
+
+    /// Get quirks of quirk profile.
+    pub fn quirks(self) -> Quirks {
+        match self {
+            QuirkProfile::Cosmac => Quirks { shift_vx: false, load_store_increment: true, jump_vx: false, vf_reset: true },
+            QuirkProfile::Schip => Quirks { shift_vx: true, load_store_increment: false, jump_vx: true, vf_reset: false },
+            QuirkProfile::XoChip => Quirks { shift_vx: false, load_store_increment: true, jump_vx: false, vf_reset: false },
+        }
+    }
+}
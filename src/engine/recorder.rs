@@ -0,0 +1,228 @@
+use std::{
+    convert::TryFrom,
+    fs,
+    io::{self, Write},
+};
+
+extern crate gif;
+
+use super::screen::Screen;
+use super::theme::Theme;
+use super::screenshot::palette_rgb;
+use super::machine::FRAME_HZ;
+
+/// Provides per-frame hook which records screen at every frame boundary.
+pub trait FrameRecorder {
+    /// Record screen of this frame. Must be called once per frame.
+    fn record(&mut self, screen: &Screen) -> Result<(), String>;
+    /// Write pending data and finish recording.
+    fn finish(&mut self) -> Result<(), String>;
+}
+
+/// Provides animated GIF recorder.
+///
+/// Screen is sampled every `every` frames, and identical consecutive samples are merged into one
+/// GIF frame with longer delay. Delay is accumulated from 60 Hz frame count, so it does not drift.
+pub struct GifRecorder {
+    encoder: Option<gif::Encoder<io::BufWriter<fs::File>>>,
+    path: String,
+    scale: usize,
+    every: u64,
+    /// Count of frames which have been recorded.
+    frame: u64,
+    /// Image size of GIF.
+    size: (u16, u16),
+    /// Palette indices of pending sample, which is not written yet.
+    pending: Option<Vec<u8>>,
+    /// Frame when the last written GIF frame ends.
+    written_until: u64,
+}
+
+impl GifRecorder {
+    /// Create new GIF recorder into given file path.
+    /// Palette is given by theme, and each pixel is scaled by integer `scale`.
+    pub fn new(path: &str, screen: &Screen, scale: usize, every: u64, theme: &Theme) -> Result<GifRecorder, String> {
+        let scale = scale.max(1);
+        let (width, height) = (screen.width() * scale, screen.height() * scale);
+        let palette: Vec<u8> = (0..4)
+            .flat_map(|planes| {
+                let (r, g, b) = palette_rgb(theme, planes);
+                vec![r, g, b]
+            })
+            .collect();
+
+        let error = |err: &dyn std::fmt::Display| format!("Could not create `{}` : {}", path, err);
+        // GIF image size is 16 bits.
+        let size = match (u16::try_from(width), u16::try_from(height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => return Err(error(&format!("scale {} makes image larger than 65535 pixels", scale))),
+        };
+        let file = fs::File::create(path).map_err(|err| error(&err))?;
+        let mut encoder = gif::Encoder::new(io::BufWriter::new(file), size.0, size.1, &palette)
+            .map_err(|err| error(&err))?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|err| error(&err))?;
+
+        Ok(GifRecorder {
+            encoder: Some(encoder),
+            path: path.to_string(),
+            scale,
+            every: every.max(1),
+            frame: 0,
+            size,
+            pending: None,
+            written_until: 0,
+        })
+    }
+
+    /// Get scaled palette indices of screen pixels.
+    fn indices(&self, screen: &Screen) -> Vec<u8> {
+        let (width, height) = (screen.width() * self.scale, screen.height() * self.scale);
+        let mut indices = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                indices.push(screen.plane_bits(x / self.scale, y / self.scale));
+            }
+        }
+        indices
+    }
+
+    /// Convert frame count into 1/100 second unit of GIF delay.
+    fn to_centiseconds(frame: u64) -> u64 {
+        ((frame as f64) * 100.0 / FRAME_HZ).round() as u64
+    }
+
+    /// Write pending sample as GIF frame which lasts until given frame.
+    fn write_pending(&mut self, until: u64) -> Result<(), String> {
+        let indices = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+        let encoder = match self.encoder.as_mut() {
+            Some(encoder) => encoder,
+            None => return Ok(()),
+        };
+
+        let (width, height) = self.size;
+        let mut delay = GifRecorder::to_centiseconds(until) - GifRecorder::to_centiseconds(self.written_until);
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, &indices, None);
+        self.written_until = until;
+        let path = &self.path;
+        // Delay of GIF frame is 16 bits, so longer sample is written as several frames.
+        loop {
+            frame.delay = delay.min(u16::MAX as u64) as u16;
+            encoder.write_frame(&frame).map_err(|err| format!("Could not write `{}` : {}", path, err))?;
+            delay -= frame.delay as u64;
+            if delay == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl FrameRecorder for GifRecorder {
+    fn record(&mut self, screen: &Screen) -> Result<(), String> {
+        let frame = self.frame;
+        self.frame += 1;
+        if !frame.is_multiple_of(self.every) {
+            return Ok(());
+        }
+
+        // Merge identical consecutive sample into pending one.
+        let indices = self.indices(screen);
+        if self.pending.as_ref() == Some(&indices) {
+            return Ok(());
+        }
+
+        self.write_pending(frame)?;
+        self.pending = Some(indices);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        let until = self.frame.max(self.written_until + 1);
+        self.write_pending(until)?;
+
+        // Dropping encoder writes GIF trailer.
+        self.encoder = None;
+        Ok(())
+    }
+}
+
+/// Provides raw video recorder which writes YUV4MPEG2 (Y4M) frame stream.
+///
+/// Every frame is written at 60 fps in 4:4:4 chroma, so stream can be piped into encoder losslessly.
+pub struct Y4mRecorder {
+    writer: Box<dyn Write>,
+    path: String,
+    scale: usize,
+    /// (Y, Cb, Cr) values of each palette entry.
+    palette: [(u8, u8, u8); 4],
+    is_header_written: bool,
+}
+
+impl Y4mRecorder {
+    /// Create new Y4M recorder into given file path. `-` path is standard output.
+    /// Palette is given by theme, and each pixel is scaled by integer `scale`.
+    pub fn new(path: &str, scale: usize, theme: &Theme) -> Result<Y4mRecorder, String> {
+        let writer: Box<dyn Write> = if path == "-" {
+            Box::new(io::BufWriter::new(io::stdout()))
+        } else {
+            let file = fs::File::create(path).map_err(|err| format!("Could not create `{}` : {}", path, err))?;
+            Box::new(io::BufWriter::new(file))
+        };
+
+        // Convert palette into BT.601 limited range YCbCr.
+        let mut palette = [(0u8, 0u8, 0u8); 4];
+        for (planes, entry) in palette.iter_mut().enumerate() {
+            let (r, g, b) = palette_rgb(theme, planes as u8);
+            let (r, g, b) = (r as f32, g as f32, b as f32);
+            let y = 16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0;
+            let cb = 128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0;
+            let cr = 128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0;
+            *entry = (y.round() as u8, cb.round() as u8, cr.round() as u8);
+        }
+
+        Ok(Y4mRecorder {
+            writer,
+            path: path.to_string(),
+            scale: scale.max(1),
+            palette,
+            is_header_written: false,
+        })
+    }
+
+    /// Write stream header and one frame of given screen.
+    fn write_frame(&mut self, screen: &Screen) -> io::Result<()> {
+        let (width, height) = (screen.width() * self.scale, screen.height() * self.scale);
+        if !self.is_header_written {
+            writeln!(self.writer, "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444", width, height, FRAME_HZ as u32)?;
+            self.is_header_written = true;
+        }
+
+        writeln!(self.writer, "FRAME")?;
+        let mut plane = Vec::with_capacity(width * height);
+        for channel in 0..3 {
+            plane.clear();
+            for y in 0..height {
+                for x in 0..width {
+                    let (luma, cb, cr) = self.palette[screen.plane_bits(x / self.scale, y / self.scale) as usize & 0b11];
+                    plane.push([luma, cb, cr][channel]);
+                }
+            }
+            self.writer.write_all(&plane)?;
+        }
+        Ok(())
+    }
+}
+
+impl FrameRecorder for Y4mRecorder {
+    fn record(&mut self, screen: &Screen) -> Result<(), String> {
+        let path = self.path.clone();
+        self.write_frame(screen).map_err(|err| format!("Could not write `{}` : {}", path, err))
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        let path = &self.path;
+        self.writer.flush().map_err(|err| format!("Could not write `{}` : {}", path, err))
+    }
+}
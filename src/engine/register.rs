@@ -1,18 +1,25 @@
 use std::fmt;
 
 extern crate rand;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
 use super::isa;
+use super::layout::MemoryLayout;
+use super::quirks::Quirks;
+use super::savestate::StateReader;
 
 /// @brief
 const GENERAL_REGISTERS_CNT: usize = 16usize;
 const STACK_POINTER_CNT: usize = 16usize;
-const INIT_PROGRAM_COUNTER_VAL: u16 = 0x200u16;
+const INIT_PITCH_VAL: u8 = 64u8;
 
 pub enum SideEffect {
     Draw{ pos: (u8, u8), n: u8, l: u16 },   // 
     ClearDisplay,                           // 
     MemDump{ dump_vals: Vec<u8>, l: u16 },  //
     MemRead{ count: u8, l: u16 },           //
+    SelectPlanes{ n: u8 },                  // Select planes of screen to draw and clear.
+    LoadAudioPattern{ l: u16 },             // Load 16 bytes audio pattern from memory.
     WaitKeyPress{ r: u8 },                  // Machine should until new key press.
     CheckKeyPressed{ key: u8 },             // Check whether key is pressed (true), or not (false).
     CheckKeyReleased{ key: u8 },            // Check whether key is pressed (false), or not (true).
@@ -26,6 +33,7 @@ pub enum TimerSideEffect {
     Beep,   
 }
 
+#[derive(Clone)]
 pub struct Registers {
     g: [u8; GENERAL_REGISTERS_CNT], // General purpose registers
                                     // Flag instruction register (carry & borrow, collision).
@@ -34,43 +42,69 @@ pub struct Registers {
     spst: Vec<u16>,                 // Stack pointer stack.
     dt: u8,                         // Delay timer register.
     st: u8,                         // Sound timer register.
+    pitch: u8,                      // Audio pattern playback pitch register. (XO-CHIP)
+    quirks: Quirks,                 // Behavior differences of platform.
+    font_address: u16,              // Address of font data in memory.
+    rng: StdRng,                    // Random number generator of RND instruction.
 }
 
 impl Registers {
-    pub fn new() -> Registers {
+    /// Create new registers with quirks. Program counter starts at entry point of memory layout.
+    /// If `seed` is given, RND instruction generates the same sequence in every run.
+    pub fn new(quirks: Quirks, layout: &MemoryLayout, seed: Option<u64>) -> Registers {
         Registers {
             g: [0; GENERAL_REGISTERS_CNT],
             sl: 0,
-            pc: INIT_PROGRAM_COUNTER_VAL,
+            pc: layout.entry_point,
             spst: Vec::<u16>::with_capacity(STACK_POINTER_CNT),
             dt: 0,
             st: 0,
+            pitch: INIT_PITCH_VAL,
+            quirks,
+            font_address: layout.font_address,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
         }
     }
 
     pub fn get_pc(&self) -> u16 { self.pc }
 
+    /// Get audio pattern playback pitch. (XO-CHIP)
+    pub fn get_pitch(&self) -> u8 { self.pitch }
+
+    /// Get delay timer value.
+    pub fn get_dt(&self) -> u8 { self.dt }
+
+    /// Get sound timer value.
+    pub fn get_st(&self) -> u8 { self.st }
+
     fn set_pc(&mut self, new_pc: u16) {
         self.pc = new_pc;
     }
 
     pub fn increase_pc(&mut self, inst_count: u16) {
-        self.pc += inst_count << 1;
+        self.pc = self.pc.wrapping_add(inst_count << 1);
     }
 
     pub fn update_vf(&mut self, is_set: bool) {
         self.set_general_register(0xFu8, if is_set { 1 } else { 0 });
     }
 
-    pub fn update_registers(&mut self, instruction: isa::Instruction) -> Option<SideEffect> {
+    /// Update registers with instruction, and return side effect which other modules should process.
+    /// If stack underflows or overflows, `Err` is returned and machine must be halted.
+    pub fn update_registers(&mut self, instruction: isa::Instruction) -> Result<Option<SideEffect>, String> {
         type Inst = isa::Instruction;
 
         let (pc_increment, side_effect) = match instruction {
             Inst::Ignore => (1, None), // 0x0___
             Inst::ClearDisplay => (1, Some(SideEffect::ClearDisplay)), // 0x00E0
             Inst::ReturnSubroutine => { // 0x00EE
-                assert!(self.spst.is_empty() == false);
-                let new_pc = self.spst.pop().unwrap();
+                let new_pc = match self.spst.pop() {
+                    Some(new_pc) => new_pc,
+                    None => return Err(format!("Stack underflow. Register dump : {}", self)),
+                };
                 self.set_pc(new_pc);
                 (1, None)
             },
@@ -79,6 +113,9 @@ impl Registers {
                 (0, None)
             },
             Inst::CallSub(new_pc) => { // 0x2nnn
+                if self.spst.len() >= STACK_POINTER_CNT {
+                    return Err(format!("Stack overflow. Register dump : {}", self));
+                }
                 self.spst.push(self.get_pc());
                 self.set_pc(new_pc);
                 (0, None)
@@ -109,14 +146,17 @@ impl Registers {
             },
             Inst::OrRegV{ r, f } => { // 0x8xy1
                 self.g[r as usize] |= self.general_register(f);
+                self.reset_vf_by_quirk();
                 (1, None)
             },
             Inst::AndRegV{ r, f } => { // 0x8xy2
                 self.g[r as usize] &= self.general_register(f);
+                self.reset_vf_by_quirk();
                 (1, None)
             },
             Inst::XorRegV{ r, f } => { // 0x8xy3
                 self.g[r as usize] ^= self.general_register(f);
+                self.reset_vf_by_quirk();
                 (1, None)
             },
             Inst::AddRegV{ r, f } => { // 0x8xy4
@@ -132,6 +172,7 @@ impl Registers {
                 (1, None)
             },
             Inst::ShrRegV{ r, f } => { // 0x8xy6
+                let f = if self.quirks.shift_vx { r } else { f };
                 self.update_vf((self.general_register(f) & 0b01) != 0);
                 let new_value = self.general_register(f) >> 1;
                 self.set_general_register(r, new_value);
@@ -144,6 +185,7 @@ impl Registers {
                 (1, None)
             },
             Inst::ShlRegV{ r, f } => { // 0x8x_E
+                let f = if self.quirks.shift_vx { r } else { f };
                 self.update_vf((self.general_register(f) & 0x80) != 0);
                 let new_value = self.general_register(f) << 1;
                 self.set_general_register(r, new_value);
@@ -158,11 +200,14 @@ impl Registers {
                 (1, None)
             },
             Inst::JmpAddrOffReg0(new_pc) => { // 0xBnnn
-                self.set_pc((self.general_register(0) as u16) + new_pc);
+                // With jump quirk, BXNN jumps to XNN + VX.
+                let r = if self.quirks.jump_vx { (new_pc >> 8) as u8 } else { 0 };
+                self.set_pc((self.general_register(r) as u16) + new_pc);
                 (0, None)
             },
             Inst::RndAnd{ r, val } => { // 0xCxkk
-                self.set_general_register(r, rand::random::<u8>() & val);
+                let value = self.rng.gen::<u8>() & val;
+                self.set_general_register(r, value);
                 (1, None)
             },
             Inst::DispSpr{ rp, n } => { // 0xDxyn
@@ -184,6 +229,8 @@ impl Registers {
                 self.set_general_register(r, self.dt);
                 (1, None)
             },
+            Inst::SelectPlanes{ n } => (1, Some(SideEffect::SelectPlanes{ n })), // 0xFn01
+            Inst::LoadAudioPattern => (1, Some(SideEffect::LoadAudioPattern{ l: self.sl })), // 0xF002
             Inst::WaitKeyPress{ r } => (1, Some(SideEffect::WaitKeyPress{ r })), // 0xFx0A
             Inst::SetDelayFromReg{ r } => { // 0xFx15
                 self.dt = self.general_register(r);
@@ -194,11 +241,11 @@ impl Registers {
                 (1, None)
             },
             Inst::AddRegL{ r } => { // 0xFx1E
-                self.sl += self.general_register(r) as u16;
+                self.sl = self.sl.wrapping_add(self.general_register(r) as u16);
                 (1, None)
             },
             Inst::SetRegLFontAddrFromReg{ r } => { // 0xFx29
-                self.sl = (self.general_register(r) as u16) * 5u16;
+                self.sl = self.font_address.wrapping_add((self.general_register(r) as u16) * 5u16);
                 (1, None)
             },
             Inst::MemDumpBcdFromReg{ r } => { // 0xFx33
@@ -211,21 +258,29 @@ impl Registers {
                 };
                 (1, Some(SideEffect::MemDump{ dump_vals: bcd_code, l: self.sl }))
             },
+            Inst::SetPitchFromReg{ r } => { // 0xFx3A
+                self.pitch = self.general_register(r);
+                (1, None)
+            },
             Inst::MemDump{ endr } => { // 0xFx55
                 let l = self.sl;
-                self.sl += (endr as u16) + 1u16;
+                if self.quirks.load_store_increment {
+                    self.sl = self.sl.wrapping_add((endr as u16) + 1u16);
+                }
                 (1, Some(SideEffect::MemDump{ dump_vals: self.g[0..=(endr as usize)].to_vec(), l }))
             },
             Inst::MemRead{ endr } => { // 0xFx65
                 let l = self.sl;
-                self.sl += (endr as u16) + 1u16;
+                if self.quirks.load_store_increment {
+                    self.sl = self.sl.wrapping_add((endr as u16) + 1u16);
+                }
                 (1, Some(SideEffect::MemRead{ count: endr + 1, l }))
             }
         };
 
         // Increase program counter and return side effect to other module.
         self.increase_pc(pc_increment);
-        side_effect
+        Ok(side_effect)
     }
 
     pub fn store_from_v0(&mut self, values: &[u8]) {
@@ -234,6 +289,62 @@ impl Registers {
         }
     }
 
+    /// Get quirks of platform.
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Set quirks of platform.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Write register values into save state.
+    pub fn write_state(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.g);
+        out.extend_from_slice(&self.sl.to_be_bytes());
+        out.extend_from_slice(&self.pc.to_be_bytes());
+        out.push(self.spst.len() as u8);
+        for address in &self.spst {
+            out.extend_from_slice(&address.to_be_bytes());
+        }
+        out.extend_from_slice(&[self.dt, self.st, self.pitch]);
+    }
+
+    /// Read register values from save state.
+    /// Registers are changed only when all values are read, so invalid state keeps the current values.
+    pub fn read_state(&mut self, reader: &mut StateReader) -> Option<()> {
+        let mut g = [0; GENERAL_REGISTERS_CNT];
+        g.copy_from_slice(reader.bytes(GENERAL_REGISTERS_CNT)?);
+        let sl = reader.u16()?;
+        let pc = reader.u16()?;
+        let depth = reader.u8()? as usize;
+        if depth > STACK_POINTER_CNT {
+            return None;
+        }
+        let mut spst = Vec::with_capacity(STACK_POINTER_CNT);
+        for _ in 0..depth {
+            spst.push(reader.u16()?);
+        }
+        let (dt, st, pitch) = (reader.u8()?, reader.u8()?, reader.u8()?);
+
+        self.g = g;
+        self.sl = sl;
+        self.pc = pc;
+        self.spst = spst;
+        self.dt = dt;
+        self.st = st;
+        self.pitch = pitch;
+        Some(())
+    }
+
+    /// Reset VF after logical instructions, if platform has the quirk.
+    fn reset_vf_by_quirk(&mut self) {
+        if self.quirks.vf_reset {
+            self.update_vf(false);
+        }
+    }
+
     /// Update timer registers.
     pub fn update_timers(&mut self) -> TimerSideEffect {
         if self.dt > 0 {
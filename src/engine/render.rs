@@ -0,0 +1,322 @@
+extern crate crossterm;
+use crossterm::style::Color;
+
+use super::screen::Screen;
+use super::device::Device;
+use super::theme::{Theme, ColorDepth, Rgb};
+
+/// Provides the way how screen pixels are rendered into terminal cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    /// One pixel per two horizontal full-block cells.
+    Block,
+    /// Two vertical pixels per cell using upper and lower half blocks.
+    HalfBlock,
+    /// 2x4 pixels per cell using braille patterns.
+    Braille,
+}
+
+impl RenderMode {
+    /// Get canonical name.
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Block => "block",
+            RenderMode::HalfBlock => "half",
+            RenderMode::Braille => "braille",
+        }
+    }
+
+    /// Get render mode from given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<RenderMode> {
+        match &name.to_lowercase()[..] {
+            "block" => Some(RenderMode::Block),
+            "half" | "halfblock" | "half-block" => Some(RenderMode::HalfBlock),
+            "braille" => Some(RenderMode::Braille),
+            _ => None,
+        }
+    }
+
+    /// Get pixel count (width, height) which is covered by one cell.
+    fn pixels_per_cell(self) -> (usize, usize) {
+        match self {
+            RenderMode::Block => (1, 1),
+            RenderMode::HalfBlock => (1, 2),
+            RenderMode::Braille => (2, 4),
+        }
+    }
+
+    /// Get terminal column count of one cell.
+    fn columns_per_cell(self) -> u16 {
+        match self {
+            RenderMode::Block => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Provides anti-flicker filter which is applied to screen pixels at frame boundary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlickerFilter {
+    /// Render screen pixels as they are.
+    Off,
+    /// Render pixel if it was drawn in either of the last two frames.
+    Persist,
+    /// Render erased pixel with fading intensity for a few frames.
+    Fade,
+}
+
+impl FlickerFilter {
+    /// Get canonical name.
+    pub fn name(self) -> &'static str {
+        match self {
+            FlickerFilter::Off => "off",
+            FlickerFilter::Persist => "persist",
+            FlickerFilter::Fade => "fade",
+        }
+    }
+
+    /// Get filter from given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<FlickerFilter> {
+        match &name.to_lowercase()[..] {
+            "off" | "none" => Some(FlickerFilter::Off),
+            "persist" => Some(FlickerFilter::Persist),
+            "fade" => Some(FlickerFilter::Fade),
+            _ => None,
+        }
+    }
+
+    /// Get intensity level of just drawn pixel.
+    /// Erased pixel loses one level per frame, and is rendered until level reaches 0.
+    fn max_level(self) -> u8 {
+        match self {
+            FlickerFilter::Off => 1,
+            FlickerFilter::Persist => 2,
+            FlickerFilter::Fade => 5,
+        }
+    }
+}
+
+/// Provides one rendered terminal cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+/// Provides screen renderer which converts screen pixels into terminal cells.
+pub struct Renderer {
+    mode: RenderMode,
+    filter: FlickerFilter,
+    theme: Theme,
+    depth: ColorDepth,
+    /// Intensity level of each screen pixel, updated at frame boundary.
+    levels: Vec<u8>,
+    /// Plane bits of each screen pixel when it was drawn lastly.
+    planes: Vec<u8>,
+    /// Screen width of `levels` as pixel unit.
+    level_width: usize,
+    /// Cells of last presented frame. `None` cell is not presented yet.
+    presented: Vec<Option<Cell>>,
+    /// Ratio which background is tinted toward foreground, ranged in [0, 1].
+    tint: f32,
+}
+
+impl Renderer {
+    /// Create new renderer with given mode, filter and theme.
+    /// Theme colors are converted with detected color capability of terminal.
+    pub fn new(mode: RenderMode, filter: FlickerFilter, theme: Theme) -> Renderer {
+        Renderer {
+            mode, filter, theme,
+            depth: ColorDepth::detect(),
+            levels: Vec::new(),
+            planes: Vec::new(),
+            level_width: 0,
+            presented: Vec::new(),
+            tint: 0.0,
+        }
+    }
+
+    /// Set ratio which background is tinted toward foreground color, for flashing display.
+    /// Changed cells are presented again at next `present()`.
+    pub fn set_tint(&mut self, tint: f32) {
+        self.tint = tint.clamp(0.0, 1.0);
+    }
+
+    /// Get terminal size (columns, rows) which is needed to render given screen.
+    pub fn display_size(&self, screen: &Screen) -> (u16, u16) {
+        let (cols, rows) = self.cell_grid_size(screen);
+        ((cols as u16) * self.mode.columns_per_cell(), rows as u16)
+    }
+
+    /// End emulated frame, and update intensity level of each pixel from screen state of this frame.
+    /// This must be called once per emulated frame, so faded pixels are kept while machine is paused.
+    pub fn end_frame(&mut self, screen: &Screen) {
+        let (width, height) = (screen.width(), screen.height());
+        if self.levels.len() != width * height {
+            self.levels = vec![0; width * height];
+            self.planes = vec![0; width * height];
+            self.level_width = width;
+        }
+
+        let max_level = self.filter.max_level();
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                let planes = screen.plane_bits(x, y);
+                if planes != 0 {
+                    self.levels[index] = max_level;
+                    self.planes[index] = planes;
+                } else {
+                    self.levels[index] = self.levels[index].saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    /// Get intensity level and color of given pixel.
+    /// Out of screen pixel has level 0, and background color.
+    fn pixel(&self, x: usize, y: usize) -> (u8, Color) {
+        let index = y * self.level_width + x;
+        match self.levels.get(index) {
+            Some(&level) if x < self.level_width && level > 0 => (level, self.pixel_color(level, self.planes[index])),
+            _ => (0, self.background()),
+        }
+    }
+
+    /// Get background color of theme.
+    fn background(&self) -> Color {
+        if self.tint <= 0.0 {
+            return self.depth.color(self.theme.palette[0]);
+        }
+
+        // Tinted background is blended with foreground.
+        let (fr, fg, fb) = self.theme.palette[1].unwrap_or((0xFF, 0xFF, 0xFF));
+        let (br, bg, bb) = self.theme.palette[0].unwrap_or((0x00, 0x00, 0x00));
+        let blend = |f: u8, b: u8| (b as f32 + (f as f32 - b as f32) * self.tint) as u8;
+        self.depth.color(Some((blend(fr, br), blend(fg, bg), blend(fb, bb))))
+    }
+
+    /// Get color of pixel which has given intensity level and plane bits.
+    /// Full level pixel uses palette entry of plane bits as it is.
+    fn pixel_color(&self, level: u8, planes: u8) -> Color {
+        let max_level = self.filter.max_level();
+        let color = self.theme.palette[planes as usize & 0b11];
+        if level >= max_level || self.filter != FlickerFilter::Fade {
+            return self.depth.color(color);
+        }
+
+        // Fading pixel is blended with background.
+        // Terminal default color is regarded as white foreground and black background.
+        let ratio = level as f32 / max_level as f32;
+        let (fr, fg, fb) = color.unwrap_or((0xFF, 0xFF, 0xFF));
+        let (br, bg, bb) = self.theme.palette[0].unwrap_or((0x00, 0x00, 0x00));
+        let blend = |f: u8, b: u8| (b as f32 + (f as f32 - b as f32) * ratio) as u8;
+        let rgb: Rgb = (blend(fr, br), blend(fg, bg), blend(fb, bb));
+        self.depth.color(Some(rgb))
+    }
+
+    /// Render cell of given cell position from pixel intensity levels.
+    fn cell(&self, (cx, cy): (usize, usize)) -> Cell {
+        let (pw, ph) = self.mode.pixels_per_cell();
+        let (x, y) = (cx * pw, cy * ph);
+
+        let background = self.background();
+        match self.mode {
+            RenderMode::Block => {
+                let (level, color) = self.pixel(x, y);
+                let glyph = if level > 0 { '\u{2588}' } else { ' ' };
+                Cell { glyph, fg: color, bg: background }
+            },
+            RenderMode::HalfBlock => {
+                let (upper, upper_color) = self.pixel(x, y);
+                let (lower, lower_color) = self.pixel(x, y + 1);
+                match (upper, lower) {
+                    (0, 0) => Cell { glyph: ' ', fg: background, bg: background },
+                    (_, 0) => Cell { glyph: '\u{2580}', fg: upper_color, bg: background },
+                    (0, _) => Cell { glyph: '\u{2584}', fg: lower_color, bg: background },
+                    _ if upper_color == lower_color => Cell { glyph: '\u{2588}', fg: upper_color, bg: background },
+                    // Both pixels are lit with different color, so paint lower one with background.
+                    _ => Cell { glyph: '\u{2580}', fg: upper_color, bg: lower_color },
+                }
+            },
+            RenderMode::Braille => {
+                // Braille dot bits are ordered as column-major 3 rows, and the 4th row at last.
+                const DOT_BITS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+                let mut bits = 0u32;
+                let mut brightest = (0, background);
+                for (dy, row) in DOT_BITS.iter().enumerate() {
+                    for (dx, &bit) in row.iter().enumerate() {
+                        let (level, color) = self.pixel(x + dx, y + dy);
+                        if level > 0 {
+                            bits |= bit as u32;
+                            if level > brightest.0 {
+                                brightest = (level, color);
+                            }
+                        }
+                    }
+                }
+                // Braille cell can have only one color, so the brightest dot is used.
+                let glyph = std::char::from_u32(0x2800 + bits).unwrap_or(' ');
+                Cell { glyph, fg: brightest.1, bg: background }
+            },
+        }
+    }
+
+    /// Get cell grid size (columns, rows) of given screen.
+    fn cell_grid_size(&self, screen: &Screen) -> (usize, usize) {
+        let (pw, ph) = self.mode.pixels_per_cell();
+        (screen.width().div_ceil(pw), screen.height().div_ceil(ph))
+    }
+
+    /// Diff screen against last presented frame, and queue changed cells into device.
+    /// Consecutive changed cells in a row are coalesced into one cursor move and print.
+    /// Pixel levels of the last `end_frame()` are drawn.
+    pub fn present(&mut self, screen: &Screen, device: &mut Device) -> Result<(), crossterm::ErrorKind> {
+        let (cols, rows) = self.cell_grid_size(screen);
+        if self.presented.len() != cols * rows {
+            self.presented = vec![None; cols * rows];
+        }
+
+        let columns = self.mode.columns_per_cell() as usize;
+        let mut colors = None;
+        let mut run = String::new();
+        for cy in 0..rows {
+            let mut cx = 0;
+            while cx < cols {
+                let cell = self.cell((cx, cy));
+                if self.presented[cy * cols + cx] == Some(cell) {
+                    cx += 1;
+                    continue;
+                }
+
+                // Start new run of changed cells.
+                device.move_to(((cx * columns) as u16, cy as u16))?;
+                while cx < cols {
+                    let cell = self.cell((cx, cy));
+                    let presented = &mut self.presented[cy * cols + cx];
+                    if *presented == Some(cell) {
+                        break;
+                    }
+                    if colors != Some((cell.fg, cell.bg)) {
+                        device.print(&run)?;
+                        run.clear();
+                        device.set_colors(cell.fg, cell.bg)?;
+                        colors = Some((cell.fg, cell.bg));
+                    }
+
+                    run.extend(std::iter::repeat_n(cell.glyph, columns));
+                    *presented = Some(cell);
+                    cx += 1;
+                }
+                device.print(&run)?;
+                run.clear();
+            }
+        }
+
+        if colors.is_some() {
+            device.reset_color()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use std::{fs, path::Path};
+
+/// Provides metadata which is parsed from ROM file name of ROM packs,
+/// such as `Maze [David Winter, 199x].ch8` or `Space Invaders [David Winter] (alt).ch8`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RomName {
+    /// Text before the first bracket or parenthesis. File stem itself if there is no tag.
+    pub title: String,
+    /// Author in `[...]` tag.
+    pub author: Option<String>,
+    /// Year in `[...]` tag. Unknown digits can be `x`, such as `199x`.
+    pub year: Option<String>,
+    /// Revision tags in `(...)`, such as `alt`, `v1.1` or `hack`.
+    pub tags: Vec<String>,
+    /// Content of sidecar `.txt` description file.
+    pub description: Option<String>,
+}
+
+impl RomName {
+    /// Parse metadata of ROM file of given path, with its sidecar description file if exists.
+    /// Sidecar file is `<rom stem>.txt` or `<rom file path>.txt` in the same directory.
+    pub fn from_path(rom_path: &str) -> RomName {
+        let path = Path::new(rom_path);
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut name = RomName::parse(&stem);
+
+        let sidecars = [path.with_extension("txt"), Path::new(&format!("{}.txt", rom_path)).to_path_buf()];
+        name.description = sidecars.iter()
+            .filter(|sidecar| sidecar.is_file())
+            .find_map(|sidecar| fs::read(sidecar).ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).trim().replace("\r\n", "\n"))
+            .filter(|text| !text.is_empty());
+        name
+    }
+
+    /// Parse metadata from file stem which does not have extension.
+    pub fn parse(stem: &str) -> RomName {
+        let mut name = RomName::default();
+        let title_end = stem.find(['[', '(']).unwrap_or(stem.len());
+        name.title = stem[..title_end].trim().to_string();
+
+        let mut rest = &stem[title_end..];
+        while let Some(start) = rest.find(['[', '(']) {
+            let close = if rest[start..].starts_with('[') { ']' } else { ')' };
+            let end = match rest[start..].find(close) {
+                Some(end) => start + end,
+                None => break,
+            };
+            let inner = rest[start + 1..end].trim();
+            match close {
+                ']' => for part in inner.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+                    match is_year(part) {
+                        true => name.year = Some(part.to_string()),
+                        false if name.author.is_none() => name.author = Some(part.to_string()),
+                        false => name.tags.push(part.to_string()),
+                    }
+                },
+                _ if !inner.is_empty() => name.tags.push(inner.to_string()),
+                _ => (),
+            }
+            rest = &rest[end + 1..];
+        }
+
+        if name.title.is_empty() {
+            name.title = stem.trim().to_string();
+        }
+        name
+    }
+
+    /// Get author and year as one line, such as `David Winter, 199x`.
+    pub fn byline(&self) -> Option<String> {
+        match (&self.author, &self.year) {
+            (Some(author), Some(year)) => Some(format!("{}, {}", author, year)),
+            (Some(text), None) | (None, Some(text)) => Some(text.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// Get title with revision tags and byline, such as `Space Invaders (alt) - David Winter`.
+    pub fn display_name(&self) -> String {
+        let mut text = self.title.clone();
+        for tag in &self.tags {
+            text.push_str(&format!(" ({})", tag));
+        }
+        if let Some(byline) = self.byline() {
+            text.push_str(&format!(" - {}", byline));
+        }
+        text
+    }
+}
+
+/// Check given text is year such as `1991`, `199x` or `19??`.
+fn is_year(text: &str) -> bool {
+    text.len() == 4
+        && text.starts_with(|chr: char| chr.is_ascii_digit())
+        && text.chars().all(|chr| chr.is_ascii_digit() || chr == 'x' || chr == 'X' || chr == '?')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_author_and_year() {
+        let name = RomName::parse("Maze [David Winter, 199x]");
+        assert_eq!(name.title, "Maze");
+        assert_eq!(name.author.as_deref(), Some("David Winter"));
+        assert_eq!(name.year.as_deref(), Some("199x"));
+        assert!(name.tags.is_empty());
+        assert_eq!(name.display_name(), "Maze - David Winter, 199x");
+    }
+
+    #[test]
+    fn parse_revision_tags() {
+        let name = RomName::parse("Space Invaders [David Winter] (alt)");
+        assert_eq!(name.title, "Space Invaders");
+        assert_eq!(name.author.as_deref(), Some("David Winter"));
+        assert_eq!(name.year, None);
+        assert_eq!(name.tags, vec!["alt"]);
+        assert_eq!(name.display_name(), "Space Invaders (alt) - David Winter");
+
+        let name = RomName::parse("Breakout (Brix hack) [David Winter, 1997]");
+        assert_eq!(name.title, "Breakout");
+        assert_eq!(name.tags, vec!["Brix hack"]);
+        assert_eq!(name.byline().as_deref(), Some("David Winter, 1997"));
+    }
+
+    #[test]
+    fn parse_plain_names() {
+        let name = RomName::parse("pong");
+        assert_eq!(name, RomName { title: String::from("pong"), ..RomName::default() });
+        assert_eq!(name.display_name(), "pong");
+
+        // Unclosed bracket is ignored, and whole stem is used as title if it has only tags.
+        assert_eq!(RomName::parse("Tetris [Fran").title, "Tetris");
+        assert_eq!(RomName::parse("[1991]").title, "[1991]");
+        assert_eq!(RomName::parse("[1991]").year.as_deref(), Some("1991"));
+    }
+}
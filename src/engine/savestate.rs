@@ -0,0 +1,138 @@
+use std::fs;
+
+use super::machine::Machine;
+use super::quirks::QuirkProfile;
+use super::screen::Screen;
+use super::state::MachineState;
+
+/// Header of save state file, with format version.
+const MAGIC: &[u8] = b"CHIPMUNK-STATE\x02";
+
+/// Provides reader of save state bytes.
+pub struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    /// Read next `count` bytes. If there is not enough bytes, `None` is returned.
+    pub fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < count {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(count);
+        self.bytes = tail;
+        Some(head)
+    }
+
+    /// Read next byte.
+    pub fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    /// Read next big endian 2 bytes.
+    pub fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Read next big endian 4 bytes.
+    pub fn u32(&mut self) -> Option<u32> {
+        self.bytes(4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// Save whole machine state into given file path.
+///
+/// Memory, registers, screen with its planes, machine state, audio pattern and quirk profile are saved.
+/// State of random number generator is not saved.
+pub fn save(machine: &Machine, path: &str) -> Result<(), String> {
+    let mut out = Vec::with_capacity(8 << 10);
+    out.extend_from_slice(MAGIC);
+    out.push(machine.profile.name().len() as u8);
+    out.extend_from_slice(machine.profile.name().as_bytes());
+
+    let memory = machine.memory.bytes();
+    out.extend_from_slice(&(memory.len() as u32).to_be_bytes());
+    out.extend_from_slice(memory);
+    machine.registers.write_state(&mut out);
+
+    let screen = &machine.screen;
+    out.push(screen.width() as u8);
+    out.push(screen.height() as u8);
+    for y in 0..screen.height() {
+        for x in 0..screen.width() {
+            out.push(screen.plane_bits(x, y));
+        }
+    }
+    out.push(screen.selected_planes());
+
+    match machine.state {
+        MachineState::Normal => out.extend_from_slice(&[0, 0]),
+        MachineState::WaitKeyPress{ r } => out.extend_from_slice(&[1, r]),
+    }
+    match &machine.audio_pattern {
+        Some(pattern) => {
+            out.push(1);
+            out.extend_from_slice(pattern);
+        },
+        None => out.push(0),
+    }
+
+    fs::write(path, out).map_err(|err| format!("Could not write save state `{}` : {}", path, err))
+}
+
+/// Load whole machine state from given save state file path.
+pub fn load(machine: &mut Machine, path: &str) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|err| format!("Could not read save state `{}` : {}", path, err))?;
+    let invalid = || format!("`{}` is not valid save state", path);
+    let mut reader = StateReader { bytes: &bytes };
+    if reader.bytes(MAGIC.len()) != Some(MAGIC) {
+        return Err(invalid());
+    }
+
+    let name_len = reader.u8().ok_or_else(invalid)? as usize;
+    let name = String::from_utf8_lossy(reader.bytes(name_len).ok_or_else(invalid)?).into_owned();
+    let profile = QuirkProfile::from_name(&name).ok_or_else(invalid)?;
+
+    let memory_len = reader.u32().ok_or_else(invalid)? as usize;
+    // State of other memory layout can not be restored.
+    if memory_len != machine.layout.memory_size {
+        return Err(invalid());
+    }
+    // Whole state is read before machine is changed, so invalid state file keeps the current machine.
+    let memory = reader.bytes(memory_len).ok_or_else(invalid)?;
+    let mut registers = machine.registers.clone();
+    registers.read_state(&mut reader).ok_or_else(invalid)?;
+
+    let (width, height) = (reader.u8().ok_or_else(invalid)? as usize, reader.u8().ok_or_else(invalid)? as usize);
+    let pixels = reader.bytes(width * height).ok_or_else(invalid)?;
+    let mut screen = Screen::new();
+    for y in 0..height {
+        for x in 0..width {
+            screen.set_plane_bits(x, y, pixels[y * width + x]);
+        }
+    }
+    screen.select_planes(reader.u8().ok_or_else(invalid)?);
+
+    let state = match (reader.u8(), reader.u8()) {
+        (Some(0), Some(_)) => MachineState::Normal,
+        (Some(1), Some(r)) if r <= 0xF => MachineState::WaitKeyPress{ r },
+        _ => return Err(invalid()),
+    };
+    let audio_pattern = match reader.u8() {
+        Some(0) => None,
+        Some(_) => {
+            let mut pattern = [0u8; 16];
+            pattern.copy_from_slice(reader.bytes(16).ok_or_else(invalid)?);
+            Some(pattern)
+        },
+        None => return Err(invalid()),
+    };
+
+    machine.memory.load_bytes(memory);
+    machine.registers = registers;
+    machine.set_profile(profile);
+    machine.screen = screen;
+    machine.state = state;
+    machine.audio_pattern = audio_pattern;
+    Ok(())
+}
@@ -3,67 +3,118 @@ pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
 
 #[derive(PartialEq)]
-pub enum PixelState {
+enum PixelState {
     Drawn,
     Erased,
 }
 
-pub struct DrawMessage {
-    pub pos: (u8, u8),
-    pub state: PixelState,
-}
+/// Plane bits of the first plane, which is the only plane of CHIP-8.
+const FIRST_PLANE: u8 = 0b01;
+/// Plane bits of both planes of XO-CHIP.
+const ALL_PLANES: u8 = 0b11;
 
 pub struct Screen {
-    screen_buffer: [bool; SCREEN_WIDTH * SCREEN_HEIGHT],
+    /// Plane bits of each pixel. Bit 0 is the first plane, and bit 1 is the second plane. (XO-CHIP)
+    screen_buffer: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+    /// Plane bits which are drawn and cleared by instructions, selected by `FN01`. (XO-CHIP)
+    selected_planes: u8,
 }
 
 impl Screen {
     pub fn new() -> Screen {
         Screen {
-            screen_buffer: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            screen_buffer: [0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            selected_planes: FIRST_PLANE,
         }
     }
 
-    fn draw_xor(&mut self, (x, y): (u8, u8)) -> PixelState {
+    fn draw_xor(&mut self, (x, y): (u8, u8), plane: u8) -> PixelState {
         let px = &mut self.screen_buffer[(y as usize) * SCREEN_WIDTH + (x as usize)];
-        if *px == true {
-            *px = false;
+        *px ^= plane;
+        if *px & plane == 0 {
             PixelState::Erased
         } else {
-            *px = true;
             PixelState::Drawn
         }
     }
 
-    pub fn draw(&mut self, (mut x, mut y): (u8, u8), bytes: &[u8]) -> (Vec<DrawMessage>, bool) {
-        let mut result = Vec::<DrawMessage>::new();
+    /// Draw sprite `bytes` at given position with xor, and return true if any pixel is erased.
+    /// Screen buffer is presented to terminal at frame boundary, not here.
+    ///
+    /// Sprite is drawn into each selected plane from the first plane, so `bytes` has rows of each plane in order.
+    /// Count of rows of one plane is `bytes.len() / selected_plane_count()`.
+    pub fn draw(&mut self, (x, y): (u8, u8), bytes: &[u8]) -> bool {
         let mut is_any_erased = false;
 
-        x %= SCREEN_WIDTH as u8;
-        y %= SCREEN_HEIGHT as u8;
+        let x = x % SCREEN_WIDTH as u8;
+        let y = y % SCREEN_HEIGHT as u8;
+
+        let rows = bytes.len() / self.selected_plane_count().max(1);
+        let selected_planes = self.selected_planes;
+        let planes = [0b01u8, 0b10u8].iter().copied().filter(|plane| selected_planes & plane != 0);
+        for (plane, plane_bytes) in planes.zip(bytes.chunks(rows.max(1))) {
+            let mut y = y;
+            for byte in plane_bytes {
+                let mut x = x;
+                for i in (0..8).rev() {
+                    if byte & (0b01 << i) != 0x00 { // XORDraw flag
+                        let state = self.draw_xor((x, y), plane);
+                        is_any_erased |= state == PixelState::Erased;
+                    }
 
-        let origx = x;
-        for byte in bytes {
-            for i in (0..8).rev() {
-                if byte & (0b01 << i) != 0x00 { // XORDraw flag
-                    let state = self.draw_xor((x, y));
-                    is_any_erased |= state == PixelState::Erased;
-                    result.push(DrawMessage{pos: (x, y), state});
-                } 
+                    x = (x + 1) % (SCREEN_WIDTH as u8);
+                }
 
-                x = (x + 1) % (SCREEN_WIDTH as u8);
+                y = (y + 1) % (SCREEN_HEIGHT as u8);
             }
+        }
+
+        is_any_erased
+    }
+
+    /// Select planes which are drawn and cleared, by plane bits `planes`. (XO-CHIP)
+    pub fn select_planes(&mut self, planes: u8) {
+        self.selected_planes = planes & ALL_PLANES;
+    }
+
+    /// Get plane bits of selected planes. (XO-CHIP)
+    pub fn selected_planes(&self) -> u8 { self.selected_planes }
 
-            x = origx;
-            y = (y + 1) % (SCREEN_HEIGHT as u8);
+    /// Get count of selected planes, which decides count of sprite rows. (XO-CHIP)
+    pub fn selected_plane_count(&self) -> usize { self.selected_planes.count_ones() as usize }
+
+    /// Get width of screen as pixel unit.
+    pub fn width(&self) -> usize { SCREEN_WIDTH }
+
+    /// Get height of screen as pixel unit.
+    pub fn height(&self) -> usize { SCREEN_HEIGHT }
+
+    /// Check whether pixel of given position is drawn in any plane or not.
+    /// Out of screen position is regarded as not drawn.
+    pub fn is_drawn(&self, x: usize, y: usize) -> bool {
+        self.plane_bits(x, y) != 0
+    }
+
+    /// Set plane bits of pixel of given position. Out of screen position is ignored.
+    pub fn set_plane_bits(&mut self, x: usize, y: usize, planes: u8) {
+        if x < SCREEN_WIDTH && y < SCREEN_HEIGHT {
+            self.screen_buffer[y * SCREEN_WIDTH + x] = planes & ALL_PLANES;
         }
+    }
 
-        (result, is_any_erased)
+    /// Get plane bits of pixel of given position. Bit 0 is the first plane, and bit 1 is the second plane.
+    /// Out of screen position is regarded as not drawn.
+    pub fn plane_bits(&self, x: usize, y: usize) -> u8 {
+        match x < SCREEN_WIDTH && y < SCREEN_HEIGHT {
+            true => self.screen_buffer[y * SCREEN_WIDTH + x],
+            false => 0,
+        }
     }
 
+    /// Clear selected planes.
     pub fn clear(&mut self) {
         for pixel in self.screen_buffer.iter_mut() {
-            *pixel = false;
+            *pixel &= !self.selected_planes;
         }
     }
 }
@@ -0,0 +1,185 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+extern crate png;
+
+use super::screen::Screen;
+use super::theme::{Theme, Rgb};
+use super::recorder::FrameRecorder;
+
+/// Get rgb color of given plane bits from theme palette.
+/// Terminal default color is regarded as white foreground and black background.
+pub fn palette_rgb(theme: &Theme, planes: u8) -> Rgb {
+    match theme.palette[planes as usize & 0b11] {
+        Some(rgb) => rgb,
+        None if planes == 0 => (0x00, 0x00, 0x00),
+        None => (0xFF, 0xFF, 0xFF),
+    }
+}
+
+/// Build RGB8 pixel data of screen, which is scaled by given integer `scale`.
+pub fn to_rgb_pixels(screen: &Screen, scale: usize, theme: &Theme) -> Vec<u8> {
+    let (width, height) = (screen.width(), screen.height());
+    let mut pixels = Vec::with_capacity(width * height * scale * scale * 3);
+
+    for y in 0..(height * scale) {
+        for x in 0..(width * scale) {
+            let (r, g, b) = palette_rgb(theme, screen.plane_bits(x / scale, y / scale));
+            pixels.extend_from_slice(&[r, g, b]);
+        }
+    }
+    pixels
+}
+
+/// Write screen as PNG image, which is scaled and colored with theme.
+fn write_png<W: Write>(w: W, screen: &Screen, scale: usize, theme: &Theme) -> Result<(), png::EncodingError> {
+    let (width, height) = (screen.width() * scale, screen.height() * scale);
+    let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&to_rgb_pixels(screen, scale, theme))
+}
+
+/// Write screen as plain PBM image. Drawn pixel is black (1) in PBM.
+fn write_pbm<W: Write>(mut w: W, screen: &Screen) -> io::Result<()> {
+    writeln!(w, "P1\n{} {}", screen.width(), screen.height())?;
+    for y in 0..screen.height() {
+        let row: Vec<&str> = (0..screen.width())
+            .map(|x| if screen.plane_bits(x, y) != 0 { "1" } else { "0" })
+            .collect();
+        writeln!(w, "{}", row.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Write screen as plain PGM image. Gray value is plane bits of pixel. (0 ~ 3)
+fn write_pgm<W: Write>(mut w: W, screen: &Screen) -> io::Result<()> {
+    writeln!(w, "P2\n{} {}\n3", screen.width(), screen.height())?;
+    for y in 0..screen.height() {
+        let row: Vec<String> = (0..screen.width())
+            .map(|x| screen.plane_bits(x, y).to_string())
+            .collect();
+        writeln!(w, "{}", row.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Get screen as text. Drawn pixel is `#`, and erased pixel is `.`.
+pub fn to_text(screen: &Screen) -> String {
+    let mut text = String::with_capacity((screen.width() + 1) * screen.height());
+    for y in 0..screen.height() {
+        text.extend((0..screen.width()).map(|x| if screen.is_drawn(x, y) { '#' } else { '.' }));
+        text.push('\n');
+    }
+    text
+}
+
+/// Get screen as braille pattern lines, which has 2x4 pixels per character.
+pub fn to_braille(screen: &Screen) -> Vec<String> {
+    // Braille dot bits are ordered as column-major 3 rows, and the 4th row at last.
+    const DOT_BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    (0..screen.height()).step_by(4)
+        .map(|y| (0..screen.width()).step_by(2)
+            .map(|x| {
+                let mut bits = 0;
+                for (dy, row) in DOT_BITS.iter().enumerate() {
+                    for (dx, &bit) in row.iter().enumerate() {
+                        if x + dx < screen.width() && y + dy < screen.height() && screen.is_drawn(x + dx, y + dy) {
+                            bits |= bit;
+                        }
+                    }
+                }
+                std::char::from_u32(0x2800 + bits).unwrap_or(' ')
+            })
+            .collect())
+        .collect()
+}
+
+/// Compare screen with plain PBM image of given path, and return count of different pixels.
+/// Image must have the same size with screen.
+pub fn compare_pbm(screen: &Screen, path: &str) -> Result<usize, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("Could not read `{}` : {}", path, err))?;
+    let invalid = || format!("`{}` is not valid plain PBM image", path);
+
+    // Comments start with `#`. Pixel values may be written without any whitespace.
+    let mut tokens = text.lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(|line| line.split_whitespace());
+    if tokens.next() != Some("P1") {
+        return Err(invalid());
+    }
+    let width: usize = tokens.next().and_then(|token| token.parse().ok()).ok_or_else(invalid)?;
+    let height: usize = tokens.next().and_then(|token| token.parse().ok()).ok_or_else(invalid)?;
+    if (width, height) != (screen.width(), screen.height()) {
+        return Err(format!("`{}` has size {}x{}, but screen is {}x{}",
+            path, width, height, screen.width(), screen.height()));
+    }
+
+    let pixels: Vec<bool> = tokens.flat_map(|token| token.chars()).map(|chr| chr == '1').collect();
+    if pixels.len() < width * height {
+        return Err(invalid());
+    }
+    let different = (0..width * height)
+        .filter(|&index| pixels[index] != screen.is_drawn(index % width, index / width))
+        .count();
+    Ok(different)
+}
+
+/// Save screen into given path.
+/// Image format is decided by file extension. (`.png`, `.pbm` or `.pgm`)
+/// `scale` and `theme` are only used by PNG.
+pub fn save(screen: &Screen, path: &str, scale: usize, theme: &Theme) -> Result<(), String> {
+    let extension = Path::new(path).extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    if !["png", "pbm", "pgm"].contains(&&extension[..]) {
+        return Err(String::from("Screenshot format must be one of .png, .pbm and .pgm"));
+    }
+
+    let file = fs::File::create(path).map_err(|err| format!("Could not create `{}` : {}", path, err))?;
+    let w = io::BufWriter::new(file);
+
+    let result = match &extension[..] {
+        "png" => write_png(w, screen, scale.max(1), theme).map_err(|err| err.to_string()),
+        "pbm" => write_pbm(w, screen).map_err(|err| err.to_string()),
+        _ => write_pgm(w, screen).map_err(|err| err.to_string()),
+    };
+    result.map_err(|err| format!("Could not write `{}` : {}", path, err))
+}
+
+/// Provides screenshot which is taken at given frame, as per-frame hook.
+pub struct ScreenshotRecorder {
+    at: u64,
+    path: String,
+    scale: usize,
+    theme: Theme,
+    /// Count of frames which have been recorded.
+    frame: u64,
+}
+
+impl ScreenshotRecorder {
+    /// Create new screenshot recorder which saves screen into `path` after `at` frames.
+    pub fn new(at: u64, path: &str, scale: usize, theme: &Theme) -> ScreenshotRecorder {
+        ScreenshotRecorder { at, path: path.to_string(), scale, theme: theme.clone(), frame: 0 }
+    }
+}
+
+impl FrameRecorder for ScreenshotRecorder {
+    fn record(&mut self, screen: &Screen) -> Result<(), String> {
+        self.frame += 1;
+        if self.frame == self.at {
+            save(screen, &self.path, self.scale, &self.theme)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    cell::Cell,
+    convert::TryFrom,
+    fs,
+    io::{self, Seek, SeekFrom, Write},
+    rc::Rc,
+};
+
+use super::machine::FRAME_HZ;
+
+/// Sample rate of generated audio.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Provides audio output which receives generated samples at every frame.
+pub trait AudioSink {
+    /// Write samples of one frame. Each sample is ranged in [-1, 1].
+    fn write(&mut self, samples: &[f32]) -> Result<(), String>;
+    /// Write pending data and finish output.
+    fn finish(&mut self) -> Result<(), String> { Ok(()) }
+}
+
+/// Provides terminal bell sink, which rings bell once when tone starts.
+/// This is fallback when audio output is not available.
+///
+/// Bell is not written here, but given `ring` flag is set, so terminal display writes bell with the frame.
+pub struct BellSink {
+    ring: Rc<Cell<bool>>,
+    is_sounding: bool,
+}
+
+impl BellSink {
+    /// Create new terminal bell sink which sets given `ring` flag when tone starts.
+    pub fn new(ring: Rc<Cell<bool>>) -> BellSink {
+        BellSink { ring, is_sounding: false }
+    }
+}
+
+impl AudioSink for BellSink {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let is_sounding = samples.iter().any(|&sample| sample != 0.0);
+        if is_sounding && !self.is_sounding {
+            self.ring.set(true);
+        }
+        self.is_sounding = is_sounding;
+        Ok(())
+    }
+}
+
+/// Convert sample ranged in [-1, 1] into signed 16-bit sample.
+fn to_pcm16(sample: f32) -> [u8; 2] {
+    ((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes()
+}
+
+/// Maximum size of WAV data bytes, which RIFF chunk size of 32 bits can have with header.
+const MAX_WAV_DATA_SIZE: u32 = u32::MAX - 36;
+
+/// Provides WAV file sink of mono 16-bit PCM.
+/// WAV file can not be larger than 4GiB, so samples after data reaches the limit are not written.
+pub struct WavSink {
+    writer: io::BufWriter<fs::File>,
+    path: String,
+    /// Count of written data bytes.
+    data_size: u32,
+}
+
+impl WavSink {
+    /// Create new WAV sink into given file path.
+    pub fn new(path: &str) -> Result<WavSink, String> {
+        let file = fs::File::create(path).map_err(|err| format!("Could not create `{}` : {}", path, err))?;
+        let mut sink = WavSink { writer: io::BufWriter::new(file), path: path.to_string(), data_size: 0 };
+        sink.write_header().map_err(|err| format!("Could not write `{}` : {}", path, err))?;
+        Ok(sink)
+    }
+
+    /// Write RIFF header with current data size.
+    fn write_header(&mut self) -> io::Result<()> {
+        let writer = &mut self.writer;
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + self.data_size).to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;                   // PCM
+        writer.write_all(&1u16.to_le_bytes())?;                   // Mono
+        writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        writer.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;      // Byte rate
+        writer.write_all(&2u16.to_le_bytes())?;                   // Block align
+        writer.write_all(&16u16.to_le_bytes())?;                  // Bits per sample
+        writer.write_all(b"data")?;
+        writer.write_all(&self.data_size.to_le_bytes())
+    }
+}
+
+impl AudioSink for WavSink {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let data_size = u32::try_from(samples.len() * 2).ok()
+            .and_then(|size| self.data_size.checked_add(size))
+            .filter(|&data_size| data_size <= MAX_WAV_DATA_SIZE);
+        let data_size = match data_size {
+            Some(data_size) => data_size,
+            None => return Ok(()),
+        };
+        for &sample in samples {
+            self.writer.write_all(&to_pcm16(sample))
+                .map_err(|err| format!("Could not write `{}` : {}", self.path, err))?;
+        }
+        self.data_size = data_size;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        // Rewrite header with final data size.
+        let path = self.path.clone();
+        self.writer.seek(SeekFrom::Start(0))
+            .and_then(|_| self.write_header())
+            .and_then(|_| self.writer.flush())
+            .map_err(|err| format!("Could not write `{}` : {}", path, err))
+    }
+}
+
+/// Provides raw signed 16-bit little endian PCM sink. `-` path is standard output.
+/// It can be piped into audio player, e.g. `aplay -f S16_LE -r 44100`.
+pub struct RawSink {
+    writer: Box<dyn Write>,
+    path: String,
+}
+
+impl RawSink {
+    /// Create new raw PCM sink into given file path.
+    pub fn new(path: &str) -> Result<RawSink, String> {
+        let writer: Box<dyn Write> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            let file = fs::File::create(path).map_err(|err| format!("Could not create `{}` : {}", path, err))?;
+            Box::new(io::BufWriter::new(file))
+        };
+        Ok(RawSink { writer, path: path.to_string() })
+    }
+}
+
+impl AudioSink for RawSink {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let bytes: Vec<u8> = samples.iter().flat_map(|&sample| to_pcm16(sample).to_vec()).collect();
+        self.writer.write_all(&bytes)
+            .and_then(|_| self.writer.flush())
+            .map_err(|err| format!("Could not write `{}` : {}", self.path, err))
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        let path = &self.path;
+        self.writer.flush().map_err(|err| format!("Could not write `{}` : {}", path, err))
+    }
+}
+
+/// Get playback rate of XO-CHIP audio pattern in bits per second from pitch register.
+pub fn pattern_rate(pitch: u8) -> f64 {
+    4000.0 * 2f64.powf((pitch as f64 - 64.0) / 48.0)
+}
+
+/// Provides sound subsystem, which generates square wave tone while sound timer is not zero.
+pub struct Sound {
+    /// Tone frequency in Hz.
+    frequency: f32,
+    /// Volume ranged in [0, 1].
+    volume: f32,
+    /// Phase of waveform ranged in [0, 1), which is kept across frames.
+    phase: f64,
+    samples: Vec<f32>,
+    sinks: Vec<Box<dyn AudioSink>>,
+}
+
+impl Sound {
+    /// Create new sound subsystem with tone frequency (Hz) and volume (0 ~ 100 percent).
+    pub fn new(frequency: f32, volume: u8) -> Sound {
+        Sound {
+            frequency: frequency.max(1.0),
+            volume: volume.min(100) as f32 / 100.0,
+            phase: 0.0,
+            samples: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Add new audio sink.
+    pub fn add_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Generate samples of one frame into all sinks.
+    /// If `is_beep` is true, tone is generated. Otherwise silence is generated.
+    ///
+    /// If XO-CHIP audio `pattern` is given with pitch, 128 bits of pattern are played from MSB
+    /// at `4000 * 2^((pitch - 64) / 48)` bits per second instead of plain square wave.
+    pub fn play_frame(&mut self, is_beep: bool, pattern: Option<(&[u8; 16], u8)>) -> Result<(), String> {
+        if self.sinks.is_empty() {
+            return Ok(());
+        }
+
+        // Phase is one cycle of square wave, or whole 128 bits of pattern.
+        let step = match pattern {
+            Some((_, pitch)) => pattern_rate(pitch) / 128.0 / SAMPLE_RATE as f64,
+            None => self.frequency as f64 / SAMPLE_RATE as f64,
+        };
+        let count = (SAMPLE_RATE as f64 / FRAME_HZ).round() as usize;
+        self.samples.clear();
+        for _ in 0..count {
+            if is_beep {
+                let is_high = match pattern {
+                    Some((bits, _)) => {
+                        let bit = (self.phase * 128.0) as usize & 0x7F;
+                        bits[bit >> 3] & (0x80 >> (bit & 0x7)) != 0
+                    },
+                    None => self.phase < 0.5,
+                };
+                self.samples.push(if is_high { self.volume } else { -self.volume });
+                self.phase = (self.phase + step).fract();
+            } else {
+                self.samples.push(0.0);
+            }
+        }
+
+        for sink in self.sinks.iter_mut() {
+            sink.write(&self.samples)?;
+        }
+        Ok(())
+    }
+
+    /// Finish all sinks.
+    pub fn finish(&mut self) -> Result<(), String> {
+        for sink in self.sinks.iter_mut() {
+            sink.finish()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Provides sink which keeps written samples.
+    struct MemorySink {
+        samples: Rc<RefCell<Vec<f32>>>,
+    }
+
+    impl AudioSink for MemorySink {
+        fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+            self.samples.borrow_mut().extend_from_slice(samples);
+            Ok(())
+        }
+    }
+
+    fn sound_with_memory_sink() -> (Sound, Rc<RefCell<Vec<f32>>>) {
+        let samples = Rc::new(RefCell::new(Vec::new()));
+        let mut sound = Sound::new(440.0, 100);
+        sound.add_sink(Box::new(MemorySink { samples: samples.clone() }));
+        (sound, samples)
+    }
+
+    #[test]
+    fn pattern_rate_of_pitch() {
+        assert_eq!(pattern_rate(64), 4000.0);
+        assert_eq!(pattern_rate(112), 8000.0);
+        assert_eq!(pattern_rate(16), 2000.0);
+    }
+
+    #[test]
+    fn play_pattern_from_msb() {
+        let (mut sound, samples) = sound_with_memory_sink();
+        // The first 64 bits are high, and they are played for 64 / 4000 seconds (705.6 samples).
+        let mut pattern = [0u8; 16];
+        pattern[..8].copy_from_slice(&[0xFF; 8]);
+        sound.play_frame(true, Some((&pattern, 64))).unwrap();
+
+        let samples = samples.borrow();
+        assert_eq!(samples.len(), 735);
+        assert!(samples[..705].iter().all(|&sample| sample == 1.0));
+        assert!(samples[706..].iter().all(|&sample| sample == -1.0));
+    }
+
+    #[test]
+    fn play_silence_without_beep() {
+        let (mut sound, samples) = sound_with_memory_sink();
+        sound.play_frame(false, Some((&[0xFF; 16], 64))).unwrap();
+        assert!(samples.borrow().iter().all(|&sample| sample == 0.0));
+    }
+}
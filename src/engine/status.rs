@@ -0,0 +1,128 @@
+use std::{path::Path, time};
+
+use super::device::Device;
+use super::machine::Machine;
+use super::state::MachineState;
+
+/// Provides status line under display, which shows machine state and performance.
+///
+/// Instructions per second, frames per second and frame time are measured over one second window,
+/// so it can be checked whether emulator is keeping up with nominal clock.
+pub struct StatusBar {
+    rom_name: String,
+    pos: (u16, u16),
+    /// Minimum width of status line as column unit.
+    width: usize,
+    window_start: time::Instant,
+    window_instructions: u64,
+    window_frames: u32,
+    /// Time when the last frame was counted.
+    last_frame: time::Instant,
+    /// The longest frame time in window as second unit.
+    window_max_frame: f64,
+    /// Speed multiplier of machine.
+    speed: f64,
+    ips: f64,
+    fps: f64,
+    /// Average and the longest frame time of the last window as millisecond unit.
+    frame_time: (f64, f64),
+    /// Status line which is presented lastly.
+    presented: String,
+}
+
+impl StatusBar {
+    /// Create new status bar of given ROM file, which is drawn at `pos` with minimum `width`.
+    pub fn new(rom_path: &str, pos: (u16, u16), width: usize, machine: &Machine) -> StatusBar {
+        let rom_name = Path::new(rom_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| rom_path.to_string());
+        let now = time::Instant::now();
+        StatusBar {
+            rom_name, pos, width,
+            window_start: now,
+            window_instructions: machine.instructions,
+            window_frames: 0,
+            last_frame: now,
+            window_max_frame: 0.0,
+            speed: 1.0,
+            ips: 0.0,
+            fps: 0.0,
+            frame_time: (0.0, 0.0),
+            presented: String::new(),
+        }
+    }
+
+    /// Set ROM name which is shown in status line instead of file name.
+    pub fn set_rom_name(&mut self, rom_name: &str) {
+        self.rom_name = rom_name.to_string();
+    }
+
+    /// Set speed multiplier which is shown in status line.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+    }
+
+    /// Count one presented frame. Performance values are updated every second.
+    pub fn count_frame(&mut self, machine: &Machine) {
+        let now = time::Instant::now();
+        let frame_time = now.duration_since(self.last_frame).as_secs_f64();
+        self.last_frame = now;
+        self.window_frames += 1;
+        self.window_max_frame = self.window_max_frame.max(frame_time);
+
+        let elapsed = now.duration_since(self.window_start).as_secs_f64();
+        if elapsed >= 1.0 {
+            self.ips = machine.instructions.saturating_sub(self.window_instructions) as f64 / elapsed;
+            self.fps = self.window_frames as f64 / elapsed;
+            self.frame_time = (elapsed * 1000.0 / self.window_frames as f64, self.window_max_frame * 1000.0);
+
+            self.window_start = now;
+            self.window_instructions = machine.instructions;
+            self.window_frames = 0;
+            self.window_max_frame = 0.0;
+        }
+    }
+
+    /// Get status line of given machine.
+    /// `state` overrides machine state text, and `beep` is appended at the end of line.
+    pub fn line(&self, machine: &Machine, state: Option<&str>, beep: &str) -> String {
+        let state = match (state, &machine.state) {
+            (Some(state), _) => state.to_string(),
+            (None, MachineState::Normal) => String::from("RUN"),
+            (None, MachineState::WaitKeyPress{ r }) => format!("WAIT FX0A V{:X}", r),
+        };
+        let line = format!(
+            " {} \u{2502} {} \u{2502} {}x \u{2502} {} IPS \u{2502} {:4.1} FPS \u{2502} {:4.1}/{:4.1} ms \u{2502} {} \u{2502} DT {:3} ST {:3} {}",
+            self.rom_name, machine.profile.name(), self.speed, format_rate(self.ips), self.fps,
+            self.frame_time.0, self.frame_time.1, state,
+            machine.registers.get_dt(), machine.registers.get_st(), beep);
+
+        // Pad line, so previous longer line does not remain.
+        let padding = self.width.saturating_sub(line.chars().count());
+        line + &" ".repeat(padding)
+    }
+
+    /// Draw status line into device if it is changed.
+    pub fn draw(&mut self, machine: &Machine, state: Option<&str>, beep: &str, device: &mut Device)
+        -> Result<(), crossterm::ErrorKind> {
+        let line = self.line(machine, state, beep);
+        if line != self.presented {
+            self.width = self.width.max(line.chars().count());
+            device.mv_print_attr(self.pos, &line, true)?;
+            self.presented = line;
+        }
+        Ok(())
+    }
+}
+
+/// Format rate value with SI prefix.
+fn format_rate(value: f64) -> String {
+    if value >= 1_000_000.0 {
+        format!("{:5.2}M", value / 1_000_000.0)
+    } else if value >= 1_000.0 {
+        format!("{:5.1}K", value / 1_000.0)
+    } else {
+        format!("{:6.0}", value)
+    }
+}
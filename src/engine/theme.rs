@@ -0,0 +1,199 @@
+use std::{env, fs, path::Path};
+
+extern crate crossterm;
+use crossterm::style::Color;
+
+/// 24-bit color as (red, green, blue).
+pub type Rgb = (u8, u8, u8);
+
+/// Provides color capability of terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Detect color capability of terminal from `COLORTERM` and `TERM` environment variables.
+    pub fn detect() -> ColorDepth {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// Convert given rgb color into nearest terminal color of this depth.
+    /// `None` is terminal default color.
+    pub fn color(self, rgb: Option<Rgb>) -> Color {
+        let (r, g, b) = match rgb {
+            Some(rgb) => rgb,
+            None => return Color::Reset,
+        };
+
+        match self {
+            ColorDepth::TrueColor => Color::Rgb { r, g, b },
+            ColorDepth::Ansi256 => {
+                // Use nearer one between 6x6x6 color cube, and 24 steps grayscale ramp.
+                let to_cube = |v: u8| if v < 48 { 0 } else if v < 115 { 1 } else { (v - 35) / 40 };
+                let cube_value = |i: u8| if i == 0 { 0 } else { 55 + i * 40 };
+                let (cr, cg, cb) = (to_cube(r), to_cube(g), to_cube(b));
+                let cube = (cube_value(cr), cube_value(cg), cube_value(cb));
+
+                let average = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+                let gray_index = if average > 238 { 23 } else { average.saturating_sub(3) / 10 };
+                let gray_value = 8 + gray_index * 10;
+
+                if distance((r, g, b), cube) <= distance((r, g, b), (gray_value, gray_value, gray_value)) {
+                    Color::AnsiValue(16 + 36 * cr + 6 * cg + cb)
+                } else {
+                    Color::AnsiValue(232 + gray_index)
+                }
+            },
+            ColorDepth::Ansi16 => {
+                const ANSI16: [(Rgb, Color); 16] = [
+                    ((0x00, 0x00, 0x00), Color::Black),
+                    ((0x80, 0x00, 0x00), Color::DarkRed),
+                    ((0x00, 0x80, 0x00), Color::DarkGreen),
+                    ((0x80, 0x80, 0x00), Color::DarkYellow),
+                    ((0x00, 0x00, 0x80), Color::DarkBlue),
+                    ((0x80, 0x00, 0x80), Color::DarkMagenta),
+                    ((0x00, 0x80, 0x80), Color::DarkCyan),
+                    ((0xC0, 0xC0, 0xC0), Color::Grey),
+                    ((0x80, 0x80, 0x80), Color::DarkGrey),
+                    ((0xFF, 0x00, 0x00), Color::Red),
+                    ((0x00, 0xFF, 0x00), Color::Green),
+                    ((0xFF, 0xFF, 0x00), Color::Yellow),
+                    ((0x00, 0x00, 0xFF), Color::Blue),
+                    ((0xFF, 0x00, 0xFF), Color::Magenta),
+                    ((0x00, 0xFF, 0xFF), Color::Cyan),
+                    ((0xFF, 0xFF, 0xFF), Color::White),
+                ];
+                ANSI16.iter()
+                    .min_by_key(|(value, _)| distance((r, g, b), *value))
+                    .map(|&(_, color)| color)
+                    .unwrap()
+            },
+        }
+    }
+}
+
+/// Get squared distance of two rgb colors.
+fn distance(lhs: Rgb, rhs: Rgb) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(lhs.0, rhs.0) + d(lhs.1, rhs.1) + d(lhs.2, rhs.2)
+}
+
+/// Provides colors of screen.
+///
+/// Palette entry is indexed by plane bits of pixel.
+/// 0 is background, 1 is the first plane, 2 is the second plane, and 3 is both planes. (XO-CHIP)
+/// `None` entry is terminal default color.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub palette: [Option<Rgb>; 4],
+}
+
+impl Theme {
+    /// Get built-in theme from given `name`. Name is not case-sensitive.
+    pub fn from_name(name: &str) -> Option<Theme> {
+        let palette = match &name.to_lowercase()[..] {
+            "default" => [None, None, Some((0xAA, 0xAA, 0xAA)), Some((0x55, 0x55, 0x55))],
+            "green" | "phosphor" => [Some((0x00, 0x14, 0x00)), Some((0x33, 0xFF, 0x33)),
+                                     Some((0x1A, 0x80, 0x1A)), Some((0xAA, 0xFF, 0xAA))],
+            "amber" => [Some((0x1A, 0x0F, 0x00)), Some((0xFF, 0xB0, 0x00)),
+                        Some((0x99, 0x60, 0x00)), Some((0xFF, 0xD8, 0x80))],
+            "lcd" => [Some((0x9B, 0xBC, 0x0F)), Some((0x30, 0x62, 0x30)),
+                      Some((0x6B, 0x8C, 0x0F)), Some((0x0F, 0x38, 0x0F))],
+            "inverted" => [Some((0xFF, 0xFF, 0xFF)), Some((0x00, 0x00, 0x00)),
+                           Some((0x80, 0x80, 0x80)), Some((0x40, 0x40, 0x40))],
+            "octo" => [Some((0x99, 0x66, 0x00)), Some((0xFF, 0xCC, 0x00)),
+                       Some((0xFF, 0x66, 0x00)), Some((0x66, 0x22, 0x00))],
+            _ => return None,
+        };
+        Some(Theme { palette })
+    }
+
+    /// Apply theme description from given `text`.
+    ///
+    /// Each line is `theme = <built-in theme name>` or `color<0~3> = #RRGGBB`.
+    /// `background` and `foreground` are aliases of `color0` and `color1`. Text after `;` is ignored.
+    pub fn apply_str(&mut self, text: &str) -> Result<(), String> {
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or("");
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (lhs, rhs) = match line.find('=') {
+                Some(pos) => (line[..pos].trim(), line[pos + 1..].trim()),
+                None => return Err(format!("line {} : expected `key = value`", line_no + 1)),
+            };
+            let index = match lhs {
+                "theme" => {
+                    *self = Theme::from_name(rhs)
+                        .ok_or_else(|| format!("line {} : unknown theme `{}`", line_no + 1, rhs))?;
+                    continue;
+                },
+                "color0" | "background" => 0,
+                "color1" | "foreground" => 1,
+                "color2" => 2,
+                "color3" => 3,
+                _ => return Err(format!("line {} : unknown key `{}`", line_no + 1, lhs)),
+            };
+            self.palette[index] = parse_rgb(rhs)
+                .ok_or_else(|| format!("line {} : invalid color `{}`", line_no + 1, rhs))?;
+        }
+        Ok(())
+    }
+
+    /// Apply theme from given built-in theme name or theme file path.
+    pub fn apply_source(&mut self, source: &str) -> Result<(), String> {
+        if let Some(theme) = Theme::from_name(source) {
+            *self = theme;
+            return Ok(());
+        }
+
+        match fs::read_to_string(source) {
+            Ok(text) => self.apply_str(&text).map_err(|err| format!("{} : {}", source, err)),
+            Err(err) => Err(format!("Could not read theme `{}` : {}", source, err)),
+        }
+    }
+
+    /// Apply per-ROM theme file if exists.
+    /// Per-ROM theme file is located at `<rom file path>.theme`.
+    pub fn apply_rom_override(&mut self, rom_path: &str) -> Result<(), String> {
+        let path = format!("{}.theme", rom_path);
+        if Path::new(&path).is_file() {
+            self.apply_source(&path)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from_name("default").unwrap()
+    }
+}
+
+/// Parse `#RRGGBB` or `default` color string.
+/// `default` is terminal default color, so `Some(None)` is returned.
+fn parse_rgb(value: &str) -> Option<Option<Rgb>> {
+    if value == "default" {
+        return Some(None);
+    }
+
+    let hex = value.trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(Some(((value >> 16) as u8, (value >> 8) as u8, value as u8)))
+}
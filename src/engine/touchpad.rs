@@ -0,0 +1,74 @@
+extern crate crossterm;
+use crossterm::event::{MouseEvent, MouseButton};
+
+use super::device::Device;
+use super::keymap::COSMAC_LAYOUT;
+
+/// Terminal cell size of one key box.
+const KEY_WIDTH: u16 = 5;
+const KEY_HEIGHT: u16 = 3;
+
+/// Provides mouse-clickable on-screen hex keypad.
+pub struct Touchpad {
+    origin: (u16, u16),
+    held: Option<u8>,
+}
+
+impl Touchpad {
+    /// Create new touchpad instance which left-top corner is located at `origin`.
+    pub fn new(origin: (u16, u16)) -> Touchpad {
+        Touchpad { origin, held: None }
+    }
+
+    /// Get the key which is held by mouse button now.
+    pub fn held(&self) -> Option<u8> { self.held }
+
+    /// Get terminal size (columns, rows) which is covered by touchpad.
+    pub fn size(&self) -> (u16, u16) {
+        (KEY_WIDTH * COSMAC_LAYOUT[0].len() as u16, KEY_HEIGHT * COSMAC_LAYOUT.len() as u16)
+    }
+
+    /// Get the key which covers given terminal cell position.
+    fn hit(&self, (col, row): (u16, u16)) -> Option<u8> {
+        if col < self.origin.0 || row < self.origin.1 {
+            return None;
+        }
+
+        let (c, r) = ((col - self.origin.0) / KEY_WIDTH, (row - self.origin.1) / KEY_HEIGHT);
+        COSMAC_LAYOUT.get(r as usize).and_then(|keys| keys.get(c as usize)).copied()
+    }
+
+    /// Update held key from given mouse event.
+    /// Return true if held key is changed, so touchpad must be redrawn.
+    pub fn update(&mut self, event: MouseEvent) -> bool {
+        let held = match event {
+            MouseEvent::Down(MouseButton::Left, col, row, _) => self.hit((col, row)),
+            MouseEvent::Drag(MouseButton::Left, col, row, _) => {
+                // Dragging out of pressed key releases it.
+                self.held.and_then(|key| self.hit((col, row)).filter(|&hit| hit == key))
+            },
+            MouseEvent::Up(MouseButton::Left, _, _, _) => None,
+            _ => self.held,
+        };
+
+        let is_changed = held != self.held;
+        self.held = held;
+        is_changed
+    }
+
+    /// Draw all key boxes into device. Held key is drawn with inversed color.
+    pub fn draw(&self, device: &mut Device) -> Result<(), crossterm::ErrorKind> {
+        for (r, keys) in COSMAC_LAYOUT.iter().enumerate() {
+            for (c, &key) in keys.iter().enumerate() {
+                let x = self.origin.0 + (c as u16) * KEY_WIDTH;
+                let y = self.origin.1 + (r as u16) * KEY_HEIGHT;
+                let is_held = self.held == Some(key);
+
+                device.mv_print_attr((x, y), "\u{250C}\u{2500}\u{2500}\u{2500}\u{2510}", is_held)?;
+                device.mv_print_attr((x, y + 1), &format!("\u{2502} {:X} \u{2502}", key), is_held)?;
+                device.mv_print_attr((x, y + 2), "\u{2514}\u{2500}\u{2500}\u{2500}\u{2518}", is_held)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use super::analysis::CodeMap;
+use super::detect::{self, Confidence};
+use super::isa;
+use super::layout::MemoryLayout;
+use super::quirks::QuirkProfile;
+
+/// Provides how serious validation issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// ROM can be run, but may not work as intended.
+    Warning,
+    /// ROM can not be run correctly.
+    Error,
+}
+
+impl Severity {
+    /// Get canonical name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// Provides one issue found by ROM validation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// Address of related instruction, if any.
+    pub address: Option<u16>,
+    pub message: String,
+}
+
+impl Issue {
+    fn new(severity: Severity, address: Option<u16>, message: String) -> Issue {
+        Issue { severity, address, message }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address {
+            Some(address) => write!(f, "{} : 0x{:03X} : {}", self.severity.name(), address, self.message),
+            None => write!(f, "{} : {}", self.severity.name(), self.message),
+        }
+    }
+}
+
+/// Validate given ROM bytes which is run with given quirk profile and memory layout.
+///
+/// Empty ROM, ROM larger than available memory and SUPER-CHIP / XO-CHIP opcodes which are not implemented in reachable
+/// code are errors. Odd length, ROM overlapping reserved regions or font, undecodable words in reachable code,
+/// jumps or calls outside of ROM image, and ROM which looks like other platform are warnings.
+pub fn validate(rom: &[u8], profile: QuirkProfile, layout: &MemoryLayout) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if rom.is_empty() {
+        issues.push(Issue::new(Severity::Error, None, String::from("ROM is empty")));
+        return issues;
+    }
+    if rom.len() > layout.max_rom_size() {
+        issues.push(Issue::new(Severity::Error, None,
+            format!("ROM is {} bytes, larger than {} bytes of available memory", rom.len(), layout.max_rom_size())));
+    }
+    if !rom.len().is_multiple_of(2) {
+        issues.push(Issue::new(Severity::Warning, None, format!("ROM has odd length of {} bytes", rom.len())));
+    }
+
+    for (start, end) in layout.overlapped_regions(rom.len()) {
+        issues.push(Issue::new(Severity::Warning, None,
+            format!("ROM overlaps reserved region 0x{:03X}-0x{:03X} of memory layout", start, end - 1)));
+    }
+
+    let code = CodeMap::walk(rom, layout.load_address, layout.entry_point);
+    for &(address, word) in &code.code {
+        if let Some(name) = detect::unsupported_opcode(word) {
+            issues.push(Issue::new(Severity::Error, Some(address),
+                format!("unsupported platform opcode {} ({:04X}) in reachable code", name, word)));
+        } else if isa::parse_instruction(&word.to_be_bytes()).is_none() {
+            issues.push(Issue::new(Severity::Warning, Some(address),
+                format!("undecodable word {:04X} in reachable code", word)));
+        }
+    }
+    for &(address, target) in &code.outside {
+        issues.push(Issue::new(Severity::Warning, Some(address),
+            format!("jump or call to 0x{:03X} outside of ROM image", target)));
+    }
+    issues.sort_by_key(|issue| (std::cmp::Reverse(issue.severity), issue.address));
+
+    let detection = detect::detect(&code);
+    if detection.profile != profile && detection.confidence >= Confidence::Medium {
+        issues.push(Issue::new(Severity::Warning, None,
+            format!("ROM looks like {} ROM ({} confidence : {}), but {} is used",
+                detection.profile.name(), detection.confidence.name(), detection.reasons.join(", "), profile.name())));
+    }
+    issues
+}
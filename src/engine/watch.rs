@@ -0,0 +1,87 @@
+use std::{fs, process, time::SystemTime};
+
+use super::timer::Timer;
+
+/// Interval of checking modification time of watched files in second.
+const CHECK_INTERVAL: f64 = 0.25;
+
+/// Provides modification watcher of ROM file and its source files, which is used by `--watch`.
+///
+/// Modification time is polled, and change is reported after the file stays unchanged for one interval,
+/// so file which is being written by assembler is not reloaded.
+pub struct FileWatcher {
+    /// (path, modification time which is seen the last) pairs.
+    files: Vec<(String, Option<SystemTime>)>,
+    /// Build command which makes ROM from source files.
+    build: Option<String>,
+    timer: Timer,
+    is_pending: bool,
+}
+
+impl FileWatcher {
+    /// Create new watcher of given files.
+    /// `build` command is run by shell before reload, if given.
+    pub fn new(paths: &[String], build: Option<String>) -> FileWatcher {
+        FileWatcher {
+            files: paths.iter().map(|path| (path.clone(), modified_time(path))).collect(),
+            build,
+            timer: Timer::from_second(CHECK_INTERVAL),
+            is_pending: false,
+        }
+    }
+
+    /// Check watched files, and return true if any file is changed and the change is settled.
+    pub fn is_changed(&mut self) -> bool {
+        if !self.timer.tick() {
+            return false;
+        }
+        let mut is_modified = false;
+        for (path, time) in self.files.iter_mut() {
+            let modified = modified_time(path);
+            if modified != *time {
+                *time = modified;
+                is_modified = true;
+            }
+        }
+
+        // Wait one more interval after the last modification.
+        match (is_modified, self.is_pending) {
+            (true, _) => { self.is_pending = true; false },
+            (false, true) => { self.is_pending = false; true },
+            (false, false) => false,
+        }
+    }
+
+    /// Read modification times of watched files again, and forget pending change.
+    /// This is called after build and reload, so ROM which is written by build command is not reported as change.
+    pub fn settle(&mut self) {
+        for (path, time) in self.files.iter_mut() {
+            *time = modified_time(path);
+        }
+        self.is_pending = false;
+    }
+
+    /// Run build command. Output of command is discarded because terminal is used by display.
+    pub fn build(&self) -> Result<(), String> {
+        let command = match &self.build {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        let status = process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .status()
+            .map_err(|err| format!("Could not run `{}` : {}", command, err))?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(format!("`{}` is failed with {}", command, status)),
+        }
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
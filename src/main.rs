@@ -1,65 +1,342 @@
-use std::env;
-use std::time;
+use std::{env, fs, process, time};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::io::{self, Write};
+use std::path::Path;
 
 mod engine;
-use engine::register::{Registers};
-use engine::memory::{Memory};
-use engine::screen::{Screen, DrawMessage, PixelState};
-use engine::keypad::Keypad;
-use engine::state::MachineState;
-use engine::check::get_ch8_file_path;
+use engine::machine::{Machine, FRAME_HZ};
+use engine::screenshot::{self, ScreenshotRecorder};
+use engine::check::{
+    parse_command_line, check_rom, CommandLine, usage, help, Arguments, Command,
+    EXIT_SUCCESS, EXIT_FAILURE, EXIT_USAGE, EXIT_INVALID_ROM,
+};
+use engine::savestate;
+use engine::disasm;
+use engine::validate::Severity;
+use engine::info::{InfoReport, ReportFormat};
+use engine::romname::RomName;
+use engine::browser::Browser;
+use engine::watch::FileWatcher;
+use engine::keymap::Keymap;
+use engine::touchpad::Touchpad;
+use engine::render::Renderer;
+use engine::theme::Theme;
+use engine::movie::Movie;
+use engine::recorder::{FrameRecorder, GifRecorder, Y4mRecorder};
+use engine::cast::CastRecorder;
+use engine::sound::{Sound, BellSink, WavSink, RawSink};
+use engine::register::TimerSideEffect;
+use engine::beep::{VisualBeep, BeepIndicator, MAX_BEEP_LEVEL};
+use engine::status::StatusBar;
 use engine::device;
 use engine::timer;
 
 extern crate crossterm;
-use crossterm::event::{poll, read, Event, KeyEvent, KeyCode};
+use crossterm::event::{poll, read, Event, KeyEvent, KeyCode, MouseEvent};
 
-fn main() {
-    // Get file path.
-    // Interpret file and check validation.
-    let mut args = env::args();
-    let file_path = match get_ch8_file_path(&mut args) {
-        Ok(path) => path,
-        Err(err_msg) => {
-            println!("{}", err_msg);
-            return;
+/// Build keymap from arguments.
+/// Bindings are applied as default layout < `--keymap` < per-ROM keymap file < `--bind`.
+fn build_keymap(arguments: &Arguments) -> Result<Keymap, String> {
+    let mut keymap = Keymap::default();
+    if let Some(source) = &arguments.keymap {
+        keymap.apply_source(source)?;
+    }
+    keymap.apply_rom_override(&arguments.file_path)?;
+    for (host, key) in &arguments.binds {
+        keymap.bind_str(host, key)?;
+    }
+
+    Ok(keymap)
+}
+
+/// Build theme from arguments.
+/// Colors are applied as default theme < `--theme` < per-ROM theme file < `--color*` options.
+fn build_theme(arguments: &Arguments) -> Result<Theme, String> {
+    let mut theme = Theme::default();
+    if let Some(source) = &arguments.theme {
+        theme.apply_source(source)?;
+    }
+    theme.apply_rom_override(&arguments.file_path)?;
+    for (name, color) in &arguments.colors {
+        theme.apply_str(&format!("{} = {}", name, color)).map_err(|err| format!("--{} : {}", name, err))?;
+    }
+
+    Ok(theme)
+}
+
+/// Build frame recorders which are requested by arguments.
+fn build_recorders(arguments: &Arguments, machine: &Machine, theme: &Theme, is_headless: bool)
+    -> Result<Vec<Box<dyn FrameRecorder>>, String> {
+    let mut recorders: Vec<Box<dyn FrameRecorder>> = Vec::new();
+    if let Some(frame) = arguments.screenshot_at {
+        let path = arguments.screenshot.clone().unwrap_or_else(|| screenshot_path(&arguments.file_path, frame));
+        recorders.push(Box::new(ScreenshotRecorder::new(frame, &path, arguments.scale, theme)));
+    }
+    if let Some(path) = &arguments.record_gif {
+        let recorder = GifRecorder::new(path, &machine.screen, arguments.scale, arguments.gif_every, theme)?;
+        recorders.push(Box::new(recorder));
+    }
+    if let Some(path) = &arguments.record_y4m {
+        // Standard output is used by terminal display unless run is headless.
+        if path == "-" && !is_headless {
+            return Err(String::from("`--record-y4m -` needs `--headless`, because terminal display uses standard output"));
         }
-    };
+        recorders.push(Box::new(Y4mRecorder::new(path, arguments.scale, theme)?));
+    }
 
-    // Set devices of CHIP-8 simulator.
-    let mut memory = Memory::new(&file_path).unwrap();
-    let mut registers = Registers::new();
-    let mut screen = Screen::new();
-    let mut keypad = Keypad::new(); // Already reseted.
-    let mut machine_state = MachineState::Normal;
-    let mut clock = timer::Timer::from_second(1.0 / 1_760_000.0);
-    let mut timer_60hz = timer::Timer::from_second(1.0 / 60.0);
+    Ok(recorders)
+}
+
+/// Build sound subsystem and audio sinks which are requested by arguments.
+/// Terminal bell is used as fallback sink only when running in terminal, which sets given `bell` flag.
+fn build_sound(arguments: &Arguments, is_headless: bool, bell: &Rc<Cell<bool>>) -> Result<Sound, String> {
+    let mut sound = Sound::new(arguments.tone, arguments.volume);
+    if !is_headless && !arguments.no_bell {
+        sound.add_sink(Box::new(BellSink::new(Rc::clone(bell))));
+    }
+    if let Some(path) = &arguments.record_wav {
+        sound.add_sink(Box::new(WavSink::new(path)?));
+    }
+    if let Some(path) = &arguments.record_pcm {
+        // Standard output is used by terminal display unless run is headless.
+        if path == "-" && !is_headless {
+            return Err(String::from("`--record-pcm -` needs `--headless`, because terminal display uses standard output"));
+        }
+        sound.add_sink(Box::new(RawSink::new(path)?));
+    }
+
+    Ok(sound)
+}
+
+/// Get default screenshot path of given ROM file and frame, in working directory.
+fn screenshot_path(file_path: &str, frame: u64) -> String {
+    let stem = Path::new(file_path).file_stem().and_then(|stem| stem.to_str()).unwrap_or("chipmunk");
+    format!("{}-{}.png", stem, frame)
+}
+
+/// Get default save state path of given ROM file, in working directory.
+fn state_path(file_path: &str) -> String {
+    let stem = Path::new(file_path).file_stem().and_then(|stem| stem.to_str()).unwrap_or("chipmunk");
+    format!("{}.state", stem)
+}
+
+/// Provides inputs and outputs which are processed at each frame boundary.
+struct FrameHooks {
+    /// Input movie which is played back.
+    input_movie: Option<Movie>,
+    /// Input movie which records pressed keys.
+    record_movie: Option<Movie>,
+    recorders: Vec<Box<dyn FrameRecorder>>,
+    sound: Sound,
+    /// Flag which is set by terminal bell sink when tone starts.
+    bell: Rc<Cell<bool>>,
+}
+
+impl FrameHooks {
+    /// Press keys of input movie at the beginning of given frame.
+    fn play_input(&self, frame: u64, machine: &mut Machine) {
+        if let Some(movie) = &self.input_movie {
+            for key in movie.keys_at(frame) {
+                machine.press_key(key);
+            }
+        }
+    }
+
+    /// Record key which is pressed by user at given frame.
+    fn record_input(&mut self, frame: u64, key: u8) {
+        if let Some(movie) = self.record_movie.as_mut() {
+            movie.press(frame, key);
+        }
+    }
+
+    /// Record screen at the end of frame.
+    fn record_frame(&mut self, machine: &Machine) -> Result<(), String> {
+        for recorder in self.recorders.iter_mut() {
+            recorder.record(&machine.screen)?;
+        }
+        Ok(())
+    }
+
+    /// Play sound of frame with timer side effect of the frame.
+    fn play_sound(&mut self, effect: TimerSideEffect, machine: &Machine) -> Result<(), String> {
+        self.sound.play_frame(matches!(effect, TimerSideEffect::Beep), machine.audio_pattern())
+    }
+
+    /// Finish all recordings.
+    fn finish(&mut self, arguments: &Arguments) -> Result<(), String> {
+        for recorder in self.recorders.iter_mut() {
+            recorder.finish()?;
+        }
+        self.sound.finish()?;
+        match (&self.record_movie, &arguments.record_input) {
+            (Some(movie), Some(path)) => movie.save(path),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Run machine without terminal until given frame, processing `instructions` per frame.
+fn run_headless(machine: &mut Machine, frames: u64, instructions: u32, hooks: &mut FrameHooks) -> Result<(), String> {
+    for frame in 0..frames {
+        hooks.play_input(frame, machine);
+        let effect = machine.run_frame(instructions)?;
+        hooks.play_sound(effect, machine)?;
+        hooks.record_frame(machine)?;
+    }
+    Ok(())
+}
+
+/// Speed multipliers which can be selected by hotkeys.
+const SPEED_STEPS: [f64; 7] = [0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+/// Index of normal speed in `SPEED_STEPS`.
+const DEFAULT_SPEED_STEP: usize = 3;
+
+/// Provides hotkeys which control running machine.
+#[derive(Clone, Copy)]
+enum Hotkey {
+    /// Pause or resume machine. (F5)
+    Pause,
+    /// Process one frame while paused. (F6)
+    AdvanceFrame,
+    /// Process one instruction while paused. (F7)
+    StepInstruction,
+    /// Save machine state into working directory. (F2)
+    SaveState,
+    /// Reload ROM and reset machine. (F8)
+    Reset,
+    /// Slow down speed one step. (F9)
+    SpeedDown,
+    /// Speed up one step. (F10)
+    SpeedUp,
+}
+
+impl Hotkey {
+    /// Get hotkey which is bound to given key code.
+    fn from_key_code(code: KeyCode) -> Option<Hotkey> {
+        match code {
+            KeyCode::F(2) => Some(Hotkey::SaveState),
+            KeyCode::F(5) => Some(Hotkey::Pause),
+            KeyCode::F(6) => Some(Hotkey::AdvanceFrame),
+            KeyCode::F(7) => Some(Hotkey::StepInstruction),
+            KeyCode::F(8) => Some(Hotkey::Reset),
+            KeyCode::F(9) => Some(Hotkey::SpeedDown),
+            KeyCode::F(10) => Some(Hotkey::SpeedUp),
+            _ => None,
+        }
+    }
+}
+
+/// Run machine in terminal until Escape key is pressed.
+fn run_terminal(machine: &mut Machine, arguments: &Arguments, keymap: &Keymap, theme: &Theme,
+    hooks: &mut FrameHooks) -> Result<(), String> {
+    let mut renderer = Renderer::new(arguments.render_mode, arguments.flicker_filter, theme.clone());
+    let speed = arguments.speed.max(f64::EPSILON);
+    let clock_hz = arguments.ipf as f64 * FRAME_HZ;
+    let mut clock = timer::Timer::from_second(1.0 / (clock_hz * speed));
+    let mut timer_60hz = timer::Timer::from_second(1.0 / (FRAME_HZ * speed));
 
     // Set ncurse window (Render & keyboard input)
-    let device = device::Device::new();
-    if let Err(err) = device {
-        println!("Error : {:?}", err);
-        return;
+    let mut device = device::Device::new().map_err(|err| format!("Error : {:?}", err))?;
+    if let Some(path) = &arguments.record_cast {
+        // Some terminals report zero size, then use conventional 80x24 size.
+        let size = match device.size() {
+            Ok((width, height)) if width > 0 && height > 0 => (width, height),
+            _ => (80, 24),
+        };
+        device.set_tee(CastRecorder::new(path, size)?);
     }
-    let mut device = device.unwrap();
     let _ = device.clear();
 
+    // On-screen keypad is located at the right side of display.
+    let display_size = renderer.display_size(&machine.screen);
+    let mut touchpad = Touchpad::new((display_size.0 + 2, 0));
+    let _ = touchpad.draw(&mut device);
+
+    // Visual beep is shown under display, or flashes display.
+    // If status bar is enabled, speaker glyph is shown at the end of status line.
+    let mut visual_beep = VisualBeep::new(arguments.visual_beep);
+    let status_width = (display_size.0 + 2 + touchpad.size().0) as usize;
+    let mut status_bar = match arguments.status_bar {
+        true => Some(StatusBar::new(&arguments.file_path, (0, display_size.1), status_width, machine)),
+        false => None,
+    };
+    if let Some(status_bar) = status_bar.as_mut() {
+        // Title of ROM database is preferred to metadata of file name.
+        let name = match &arguments.rom_entry {
+            Some(entry) => entry.title.clone(),
+            None => RomName::from_path(&arguments.file_path).display_name(),
+        };
+        status_bar.set_rom_name(&name);
+    }
+
+    // Frame count, and written byte count to terminal.
+    let mut frame = 0u64;
+    let mut written_bytes = 0u64;
+    let mut result = Ok(());
+    hooks.play_input(frame, machine);
+
+    // Machine can be paused and advanced manually, and speed is changed in discrete steps.
+    // Hotkeys continue from the nearest speed step of given speed.
+    let mut is_paused = false;
+    let mut speed_step = (0..SPEED_STEPS.len())
+        .min_by(|&a, &b| (SPEED_STEPS[a] - speed).abs().total_cmp(&(SPEED_STEPS[b] - speed).abs()))
+        .unwrap_or(DEFAULT_SPEED_STEP);
+    if let Some(status_bar) = status_bar.as_mut() {
+        status_bar.set_speed(speed);
+    }
+
+    // ROM and its source file are reloaded when they are changed.
+    // Keys pressed in this session are kept to re-apply them after reload.
+    let mut watcher = match arguments.watch || arguments.watch_source.is_some() {
+        true => {
+            let paths: Vec<String> = Some(arguments.file_path.clone()).into_iter()
+                .chain(arguments.watch_source.clone())
+                .collect();
+            Some(FileWatcher::new(&paths, arguments.build.clone()))
+        },
+        false => None,
+    };
+    let mut session_movie = Movie::new();
+    let mut watch_error = None;
+
     // Start one frame.
     loop {
-        if clock.tick() == false {
+        if !clock.tick() {
             continue;
         }
 
+        let mut hotkey = None;
         let input_keyval = match poll(time::Duration::from_secs(0)) {
             Ok(true) => {
                 // calling read() will be unblocked because some input is already polled.
                 match read().unwrap() {
-                    // If read value has KeyCode::Char(), try to update keypad state.
-                    Event::Key(KeyEvent{ code: KeyCode::Char(val), modifiers: _ }) => {
-                        keypad.set_press(val)
-                    },
                     // If Escape key is pressed, terminate program.
                     Event::Key(KeyEvent{ code: KeyCode::Esc, modifiers: _ }) => break,
+                    // If F12 key is pressed, save screenshot of current screen into working directory.
+                    Event::Key(KeyEvent{ code: KeyCode::F(12), modifiers: _ }) => {
+                        let path = screenshot_path(&arguments.file_path, frame);
+                        let _ = screenshot::save(&machine.screen, &path, arguments.scale, theme);
+                        None
+                    },
+                    // If hotkey is pressed, control machine. Otherwise try to update keypad state.
+                    Event::Key(KeyEvent{ code, modifiers: _ }) => {
+                        hotkey = Hotkey::from_key_code(code);
+                        match hotkey {
+                            Some(_) => None,
+                            None => keymap.key_of(code),
+                        }
+                    },
+                    // If mouse button is pressed or released on the touchpad, update held key.
+                    Event::Mouse(event) => {
+                        if touchpad.update(event) {
+                            let _ = touchpad.draw(&mut device);
+                        }
+                        match event {
+                            MouseEvent::Down(..) => touchpad.held(),
+                            _ => None,
+                        }
+                    },
                     _ => None,
                 }
             },
@@ -67,101 +344,400 @@ fn main() {
             _ => break,
         };
 
-        // If machine state is waiting for key press, and some valueable key is pressed,
-        // Change machine state and process side-effect.
-        if let MachineState::WaitKeyPress{ r } = machine_state {
-            if let Some(keyval) = input_keyval {
-                registers.set_general_register(r, keyval);
-                machine_state = MachineState::Normal;
-            }
+        // If some valueable key is pressed, update keypad state.
+        // Key held on the touchpad keeps pressed until mouse button is released.
+        if let Some(key) = input_keyval {
+            machine.press_key(key);
+            hooks.record_input(frame, key);
+            session_movie.press(frame, key);
+        }
+        if let Some(key) = touchpad.held() {
+            machine.keypad.set_press(key);
         }
 
-        if machine_state == MachineState::Normal {
-            // Parse instruction and process.
-            if let Some(instruction) = memory.parse_instruction(registers.get_pc()) {
-                use engine::register::SideEffect;
+        // Process hotkey. Frame advance and instruction step are only available while paused.
+        let mut is_redraw_needed = hotkey.is_some();
+        let mut advance = None;
+        match hotkey {
+            Some(Hotkey::Pause) => is_paused = !is_paused,
+            Some(advance_hotkey @ Hotkey::AdvanceFrame) | Some(advance_hotkey @ Hotkey::StepInstruction)
+                if is_paused => advance = Some(advance_hotkey),
+            Some(Hotkey::SaveState) => {
+                let _ = savestate::save(machine, &state_path(&arguments.file_path));
+            },
+            Some(Hotkey::Reset) => {
+                if let Err(err_msg) = machine.reset(&arguments.file_path) {
+                    result = Err(err_msg);
+                    break;
+                }
+            },
+            Some(Hotkey::SpeedDown) | Some(Hotkey::SpeedUp) => {
+                speed_step = match hotkey {
+                    Some(Hotkey::SpeedDown) => speed_step.saturating_sub(1),
+                    _ => (speed_step + 1).min(SPEED_STEPS.len() - 1),
+                };
+                let speed = SPEED_STEPS[speed_step];
+                clock = timer::Timer::from_second(1.0 / (clock_hz * speed));
+                timer_60hz = timer::Timer::from_second(1.0 / (FRAME_HZ * speed));
+                if let Some(status_bar) = status_bar.as_mut() {
+                    status_bar.set_speed(speed);
+                }
+            },
+            _ => (),
+        }
 
-                // Update register with instruction.
-                let side_effect = registers.update_registers(instruction);
+        // Reload changed ROM. Failed build or reload keeps the current machine.
+        // Watched files are read again after build and reload, so ROM written by build is not reloaded twice.
+        if watcher.as_mut().is_some_and(|watcher| watcher.is_changed()) {
+            let reloaded = watcher.as_ref().map_or(Ok(()), |watcher| watcher.build())
+                .and_then(|_| reload_rom(machine, arguments, frame, hooks, &session_movie));
+            if let Some(watcher) = watcher.as_mut() {
+                watcher.settle();
+            }
+            match reloaded {
+                Ok(reloaded) => {
+                    *machine = reloaded;
+                    renderer.end_frame(&machine.screen);
+                    watch_error = None;
+                    // Without replay, reloaded ROM starts from the first frame of input movie.
+                    if !arguments.watch_replay {
+                        frame = 0;
+                        session_movie = Movie::new();
+                    }
+                },
+                Err(err_msg) => watch_error = Some(err_msg),
+            }
+            is_redraw_needed = true;
+        }
 
-                // Process consequential side effects.
-                match side_effect {
-                    Some(SideEffect::ClearDisplay) => {
-                        screen.clear();
-                        let _ = device.clear();
-                    },
-                    Some(SideEffect::Draw{ pos, n, l: addr }) => {
-                        // Update screen buffer and get dirty pixels to update window buffer.
-                        // New carry flag value will be returned.
-                        let (dirty_pixels, is_any_erased) = screen.draw(
-                            pos, 
-                            &memory.get_data_bytes(addr as usize, n as usize)
-                        );
-
-                        // Update VF (carry & borrow flag)
-                        registers.update_vf(is_any_erased);
-
-                        // Update window buffer.
-                        for DrawMessage { pos, state } in &dirty_pixels {
-                            match state {
-                                PixelState::Erased => { 
-                                    let _ = device.mv_print(*pos, " "); 
-                                    () 
-                                },
-                                PixelState::Drawn => { 
-                                    let _ = device.mv_print(*pos, "\u{2588}"); 
-                                    () 
-                                },
-                            }
-                        }
-                    },
-                    Some(SideEffect::MemDump{ dump_vals, l }) => {
-                        // 
-                        memory.store_from(&dump_vals, l);
-                    },
-                    Some(SideEffect::MemRead{ count, l }) => {
-                        // First, get values from memory [l, l + count)
-                        // Second, store from v0 to v0 + (count - 1).
-                        registers.store_from_v0(&memory.get_data_bytes(l as usize, count as usize));
-                    },
-                    Some(SideEffect::WaitKeyPress{ r }) => {
-                        // Let machine wait for new key press.
-                        machine_state = MachineState::WaitKeyPress{ r };
-                    },
-                    Some(SideEffect::CheckKeyPressed{ key }) => {
-                        match keypad.check_press(key) {
-                            true => registers.increase_pc(2),
-                            false => registers.increase_pc(1),
-                        }
+        // Process instructions, and get timer side effect if frame is ended.
+        let processed = match (is_paused, advance) {
+            (false, _) => machine.step().map(|_| match timer_60hz.tick() {
+                true => Some(machine.update_timers()),
+                false => None,
+            }),
+            (true, Some(Hotkey::AdvanceFrame)) => machine.run_frame(arguments.ipf).map(Some),
+            (true, Some(_)) => machine.step().map(|_| None),
+            (true, None) => Ok(None),
+        };
+        let effect = match processed {
+            Ok(effect) => effect,
+            Err(err_msg) => {
+                // Failure. Abort program.
+                result = Err(err_msg);
+                break;
+            },
+        };
+
+        let is_frame_end = effect.is_some();
+        // Instruction stepped while paused is also shown, as if its frame is ended.
+        if is_frame_end || (is_paused && advance.is_some()) {
+            renderer.end_frame(&machine.screen);
+        }
+        if let Some(effect) = effect {
+            if visual_beep.update(matches!(effect, TimerSideEffect::Beep)) {
+                match visual_beep.indicator {
+                    BeepIndicator::Off => (),
+                    BeepIndicator::Glyph if status_bar.is_some() => (),
+                    BeepIndicator::Glyph => {
+                        let _ = device.mv_print_attr((0, display_size.1), &visual_beep.glyph(), false);
                     },
-                    Some(SideEffect::CheckKeyReleased{ key }) => {
-                        match keypad.check_press(key) {
-                            false => registers.increase_pc(2),
-                            true => registers.increase_pc(1),
-                        }
+                    BeepIndicator::Flash => {
+                        renderer.set_tint(visual_beep.level() as f32 / MAX_BEEP_LEVEL as f32 * 0.5);
                     },
-                    None => (),
                 }
-            } else { 
-                // Failure. Abort program.
-                println!("Register dump : {}", registers);
+            }
+            if let Err(err_msg) = hooks.play_sound(effect, machine) {
+                result = Err(err_msg);
                 break;
             }
+            // Bell is written with display of this frame, so it is also recorded into cast.
+            if hooks.bell.take() {
+                let _ = device.print("\x07");
+            }
+            is_redraw_needed = true;
         }
 
-        // Process delay / sound timer decreasement.
-        // Unlike instruction parsing and update, timer must be processed independently.
-        // Even machine state is being waited for key input, timer will be processed.
-        if timer_60hz.tick() == true {
-            use engine::register::TimerSideEffect;
-            match registers.update_timers() {
-                TimerSideEffect::None => (),
-                TimerSideEffect::Beep => (),
+        if is_redraw_needed {
+            // Present changed cells of this frame into terminal with one write.
+            let _ = renderer.present(&machine.screen, &mut device);
+            if let Some(status_bar) = status_bar.as_mut() {
+                let beep = match visual_beep.indicator {
+                    BeepIndicator::Glyph => visual_beep.glyph(),
+                    _ => String::new(),
+                };
+                if is_frame_end {
+                    status_bar.count_frame(machine);
+                }
+                let state = match (is_paused, &watch_error) {
+                    (true, _) => Some("PAUSED"),
+                    (false, Some(_)) => Some("RELOAD FAILED"),
+                    (false, None) => None,
+                };
+                let _ = status_bar.draw(machine, state, &beep, &mut device);
+            }
+            if let Ok(written) = device.present() {
+                written_bytes += written as u64;
             }
         }
 
-        // Terminate local frame states.
-        // Keypad reset should also be processed independently.
-        keypad.reset_all();
+        if is_frame_end {
+            if let Err(err_msg) = hooks.record_frame(machine) {
+                result = Err(err_msg);
+                break;
+            }
+
+            // Keys are held until the end of frame as `Machine::run_frame` does,
+            // so input movie is replayed the same way in terminal and without terminal.
+            frame += 1;
+            machine.keypad.reset_all();
+            hooks.play_input(frame, machine);
+        }
     }   // End of one frame.
+
+    // Leave terminal screen before printing statistics.
+    drop(device);
+    if let Some(err_msg) = watch_error {
+        eprintln!("The last reload is failed : {}", err_msg);
+    }
+    if arguments.render_stats {
+        println!("Presented {} frames, {} bytes written ({:.1} bytes/frame)",
+            frame, written_bytes, written_bytes as f64 / frame.max(1) as f64);
+    }
+    result
+}
+
+/// Create new machine which reloads ROM with quirks of given machine, as `--watch` is given.
+/// With `--watch-replay`, inputs of input movie and this session are re-applied without terminal until given frame.
+/// Given machine is not changed, so it can keep running if reload is failed.
+fn reload_rom(machine: &Machine, arguments: &Arguments, frame: u64, hooks: &FrameHooks, session_movie: &Movie)
+    -> Result<Machine, String> {
+    arguments.check_refused()?;
+    let mut reloaded = Machine::new(&arguments.file_path, machine.profile, machine.layout.clone(), arguments.seed)
+        .ok_or_else(|| format!("Could not reload `{}`", arguments.file_path))?;
+    reloaded.set_quirks(machine.registers.quirks());
+    reloaded.instructions = machine.instructions;
+    if arguments.watch_replay {
+        for replayed in 0..frame {
+            hooks.play_input(replayed, &mut reloaded);
+            for key in session_movie.keys_at(replayed) {
+                reloaded.press_key(key);
+            }
+            reloaded.run_frame(arguments.ipf)?;
+        }
+    }
+    Ok(reloaded)
+}
+
+/// Create machine from arguments, and load save state if it is given.
+fn build_machine(arguments: &Arguments) -> Result<Machine, String> {
+    let mut machine = Machine::new(&arguments.file_path, arguments.quirks, arguments.memory_layout(), arguments.seed)
+        .ok_or_else(|| format!("Could not load `{}`", arguments.file_path))?;
+    if let Some(path) = &arguments.load_state {
+        savestate::load(&mut machine, path)?;
+    }
+    // Profile of save state may differ from selected one.
+    machine.set_quirks(arguments.quirks_of(machine.profile));
+    Ok(machine)
+}
+
+/// Run ROM in terminal, or without terminal if headless frame count or screenshot frame is given.
+fn run(arguments: &Arguments) -> Result<(), String> {
+    let keymap = build_keymap(arguments)?;
+    let theme = build_theme(arguments)?;
+    let mut machine = build_machine(arguments)?;
+
+    // Set inputs and outputs of each frame.
+    let headless_frames = match (arguments.headless, arguments.screenshot_at) {
+        (Some(frames), Some(frame)) => Some(frames.max(frame)),
+        (frames, frame) => frames.or(frame),
+    };
+    let bell = Rc::new(Cell::new(false));
+    let mut hooks = FrameHooks {
+        input_movie: arguments.input_movie.as_ref().map(|path| Movie::load(path)).transpose()?,
+        record_movie: arguments.record_input.as_ref().map(|_| Movie::new()),
+        recorders: build_recorders(arguments, &machine, &theme, headless_frames.is_some())?,
+        sound: build_sound(arguments, headless_frames.is_some(), &bell)?,
+        bell,
+    };
+
+    let result = match headless_frames {
+        Some(frames) => run_headless(&mut machine, frames, arguments.ipf, &mut hooks),
+        None => run_terminal(&mut machine, arguments, &keymap, &theme, &mut hooks),
+    };
+    result.and(hooks.finish(arguments))
+}
+
+/// Print disassembly listing of ROM.
+fn run_disasm(arguments: &Arguments) -> Result<(), String> {
+    let rom = fs::read(&arguments.file_path)
+        .map_err(|err| format!("Could not read `{}` : {}", arguments.file_path, err))?;
+    let stdout = io::stdout();
+    // Closed pipe (e.g. `| head`) is not an error.
+    let _ = disasm::write_listing(stdout.lock(), &rom, arguments.memory_layout().load_address);
+    Ok(())
+}
+
+/// Print information report of ROM as text or JSON.
+fn run_info(arguments: &Arguments) -> Result<(), String> {
+    let rom = fs::read(&arguments.file_path)
+        .map_err(|err| format!("Could not read `{}` : {}", arguments.file_path, err))?;
+    let report = InfoReport::new(&arguments.file_path, &rom, &arguments.rom_hash, arguments.quirks, &arguments.memory_layout(),
+        arguments.rom_entry.as_ref(), arguments.detection.as_ref());
+    let text = match arguments.format {
+        ReportFormat::Text => report.to_text(),
+        ReportFormat::Json => report.to_json().to_pretty_string(),
+    };
+    // Closed pipe (e.g. `| head`) is not an error.
+    let _ = writeln!(io::stdout(), "{}", text);
+    Ok(())
+}
+
+/// Run ROM without terminal as fast as possible, and print performance.
+fn run_bench(arguments: &Arguments) -> Result<(), String> {
+    let mut machine = build_machine(arguments)?;
+    let instructions = arguments.ipf;
+    let start = time::Instant::now();
+    for _ in 0..arguments.frames {
+        machine.run_frame(instructions)?;
+    }
+
+    let elapsed = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    println!("Ran {} frames ({} instructions) in {:.3} s", arguments.frames, machine.instructions, elapsed);
+    println!("{:.1} frames/s, {:.2}M instructions/s, {:.2}x realtime",
+        arguments.frames as f64 / elapsed,
+        machine.instructions as f64 / elapsed / 1_000_000.0,
+        arguments.frames as f64 / FRAME_HZ / elapsed);
+    Ok(())
+}
+
+/// Run ROM without terminal, and print the last screen.
+/// If expected screen is given, it is compared with the last screen.
+fn run_test(arguments: &Arguments) -> Result<(), String> {
+    let mut machine = build_machine(arguments)?;
+    let mut hooks = FrameHooks {
+        input_movie: arguments.input_movie.as_ref().map(|path| Movie::load(path)).transpose()?,
+        record_movie: None,
+        recorders: Vec::new(),
+        sound: Sound::new(arguments.tone, arguments.volume),
+        bell: Rc::new(Cell::new(false)),
+    };
+    run_headless(&mut machine, arguments.frames, arguments.ipf, &mut hooks)?;
+    let _ = write!(io::stdout(), "{}", screenshot::to_text(&machine.screen));
+
+    if let Some(path) = &arguments.expect {
+        let different = screenshot::compare_pbm(&machine.screen, path)?;
+        if different > 0 {
+            return Err(format!("Screen differs from `{}` at {} pixels", path, different));
+        }
+        println!("Screen matches `{}`", path);
+    }
+    Ok(())
+}
+
+/// Print effective settings of ROM as TOML, with applied config layers.
+fn run_config_show(arguments: &Arguments) -> Result<(), String> {
+    let mut text = format!("# ROM  : {}\n# SHA-1 : {}\n# Memory : {}\n",
+        arguments.file_path, arguments.rom_hash, arguments.memory_layout().summary());
+    if arguments.config_layers.is_empty() {
+        text.push_str("# Applied : defaults only\n");
+    }
+    for layer in &arguments.config_layers {
+        text.push_str(&format!("# Applied : {}\n", layer));
+    }
+    let _ = writeln!(io::stdout(), "{}\n{}", text, arguments.settings_toml());
+    Ok(())
+}
+
+/// Show ROM browser, and run selected ROM until browser is quitted.
+/// Quitting game returns to browser, and error of the game is shown in browser.
+fn run_browser(command_line: &CommandLine) -> Result<(), String> {
+    let dir = command_line.file_path.clone().unwrap_or_else(|| String::from("."));
+    let mut browser = Browser::new(&dir)?;
+    while let Some(path) = browser.run(&|path| command_line.resolve(path), &build_machine)? {
+        let arguments = match command_line.resolve(&path) {
+            Ok(arguments) => arguments,
+            Err(err_msg) => { browser.set_message(&err_msg); continue; },
+        };
+        let result = arguments.check_refused().and_then(|_| run(&arguments));
+        if let Err(err_msg) = result {
+            browser.set_message(&err_msg);
+        }
+    }
+    Ok(())
+}
+
+/// Maximum count of validation issues which are printed before running.
+const MAX_PRINTED_ISSUES: usize = 10;
+
+fn main() {
+    // Parse arguments, and check ROM file is valid.
+    let mut args = env::args();
+    let command_line = match parse_command_line(&mut args) {
+        Ok(command_line) => command_line,
+        Err(err_msg) => {
+            eprintln!("{}\n{}", err_msg, usage());
+            process::exit(EXIT_USAGE);
+        }
+    };
+    if command_line.command == Command::Help {
+        // Closed pipe (e.g. `| head`) is not an error.
+        let _ = writeln!(io::stdout(), "{}", help());
+        process::exit(EXIT_SUCCESS);
+    }
+    if command_line.command == Command::Browse {
+        if let Err(err_msg) = run_browser(&command_line) {
+            eprintln!("{}", err_msg);
+            process::exit(EXIT_FAILURE);
+        }
+        process::exit(EXIT_SUCCESS);
+    }
+    let file_path = command_line.file_path.clone().unwrap_or_default();
+    // Settings are resolved from contents of ROM, so missing ROM is reported before resolving.
+    if !Path::new(&file_path).is_file() {
+        eprintln!("`{}` is not valid CHIP-8 file", file_path);
+        process::exit(EXIT_INVALID_ROM);
+    }
+    let arguments = match command_line.resolve(&file_path) {
+        Ok(arguments) => arguments,
+        Err(err_msg) => {
+            eprintln!("{}\n{}", err_msg, usage());
+            process::exit(EXIT_USAGE);
+        }
+    };
+    let issues = match check_rom(&arguments.file_path, arguments.quirks, &arguments.memory_layout()) {
+        Ok(issues) => issues,
+        Err(err_msg) => {
+            eprintln!("{}", err_msg);
+            process::exit(EXIT_INVALID_ROM);
+        }
+    };
+    // Info report includes all issues by itself.
+    let printed = if arguments.command == Command::Info { 0 } else { MAX_PRINTED_ISSUES };
+    for issue in issues.iter().take(printed) {
+        eprintln!("{} : {}", arguments.file_path, issue);
+    }
+    if printed > 0 && issues.len() > printed {
+        eprintln!("{} : ... and {} more issues", arguments.file_path, issues.len() - printed);
+    }
+    // Only commands which run machine are refused by validation.
+    let is_running = matches!(arguments.command, Command::Run | Command::Bench | Command::Test);
+    let is_refused = issues.iter().any(|issue| issue.severity == Severity::Error || arguments.strict);
+    if is_running && is_refused {
+        eprintln!("`{}` is refused to run by validation", arguments.file_path);
+        process::exit(EXIT_INVALID_ROM);
+    }
+
+    let result = match arguments.command {
+        Command::Disasm => run_disasm(&arguments),
+        Command::Info => run_info(&arguments),
+        Command::Bench => run_bench(&arguments),
+        Command::Test => run_test(&arguments),
+        Command::ConfigShow => run_config_show(&arguments),
+        _ => run(&arguments),
+    };
+    if let Err(err_msg) = result {
+        eprintln!("{}", err_msg);
+        process::exit(EXIT_FAILURE);
+    }
 }